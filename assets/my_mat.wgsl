@@ -1,6 +1,5 @@
-#import bevy_render::view::View;
-
 #import bevy_pbr::{
+    mesh_view_bindings::view,
     pbr_fragment::pbr_input_from_standard_material,
     pbr_functions::alpha_discard,
 }
@@ -12,14 +11,13 @@
 
 struct MyExtendedMaterial {
     quantize_steps: u32,
+    rim_strength: f32,
+    rim_power: f32,
 }
 
 @group(2) @binding(100)
 var<uniform> my_extended_material: MyExtendedMaterial;
 
-@group(2) @binding(100)
-var<uniform> view: View;
-
 @fragment
 fn fragment(
     in: VertexOutput,
@@ -50,10 +48,9 @@ fn fragment(
     let normal = normalize(in.world_normal);
     let view_dir = normalize(view.world_position.xyz - in.world_position.xyz);
     let rim = 1.0 - dot(normal, view_dir);
-    let rim_power = 3.0;
-    let rim_intensity = pow(clamp(rim, 0.0, 1.0), rim_power);
+    let rim_intensity = pow(clamp(rim, 0.0, 1.0), my_extended_material.rim_power);
     let rim_color = vec3<f32>(1.0); // White rim light
-    color += rim_color * rim_intensity * 0.3; // Adjust rim strength
+    color += rim_color * rim_intensity * my_extended_material.rim_strength;
 
     out.color = vec4<f32>(color, out.color.a);
 
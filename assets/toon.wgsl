@@ -1,31 +1,47 @@
+#import bevy_pbr::mesh_functions::{
+    get_world_from_local,
+    mesh_normal_local_to_world,
+    mesh_position_local_to_clip,
+    mesh_position_local_to_world,
+}
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+};
+
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) world_pos: vec3<f32>,
     @location(1) normal: vec3<f32>,
 };
 
-@group(1) @binding(0)
-var<uniform> LightDirection: vec3<f32>;
+// Mirrors `ToonMaterial`, every field is part of binding 0
+struct ToonMaterial {
+    light_direction: vec3<f32>,
+    base_color: vec4<f32>,
+};
 
-@group(1) @binding(1)
-var<uniform> BaseColor: vec4<f32>;
+@group(2) @binding(0)
+var<uniform> material: ToonMaterial;
 
 @vertex
-fn vertex_main(
-    @location(0) position: vec3<f32>,
-    @location(1) normal: vec3<f32>
-) -> VertexOutput {
+fn vertex(vertex: Vertex) -> VertexOutput {
     var out: VertexOutput;
-    out.position = vec4(position, 1.0);
-    out.world_pos = position;
-    out.normal = normalize(normal);
+    let world_from_local = get_world_from_local(vertex.instance_index);
+    let position = vec4(vertex.position, 1.0);
+    out.position = mesh_position_local_to_clip(world_from_local, position);
+    out.world_pos = mesh_position_local_to_world(world_from_local, position).xyz;
+    out.normal = mesh_normal_local_to_world(vertex.normal, vertex.instance_index);
     return out;
 }
 
 @fragment
-fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let light_dir = normalize(LightDirection);
-    let ndotl = max(dot(in.normal, light_dir), 0.0);
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let light_dir = normalize(material.light_direction);
+    // Interpolation shortens the normals, normalize again per fragment
+    let ndotl = max(dot(normalize(in.normal), light_dir), 0.0);
 
     // Cel shading: 3-band light steps
     let shade = select(
@@ -34,7 +50,5 @@ fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
         ndotl > 0.75
     );
 
-    // Gold tone with cartoon style
-    let base_color = BaseColor.rgb;
-    return vec4(base_color * shade, 1.0);
+    return vec4(material.base_color.rgb * shade, material.base_color.a);
 }
@@ -0,0 +1,260 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::big_number::BigNum;
+use crate::economy::Wallet;
+use crate::map_legend::MaskLegend;
+use crate::mine_plugin::RockKind;
+use crate::quest::QuestLog;
+use crate::ron_asset::RonAssetPlugin;
+use crate::statistics::Statistics;
+
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+pub struct AchievementsPlugin;
+
+#[derive(Deserialize, Debug, Clone)]
+pub enum AchievementCondition {
+    RocksBroken { kind: Option<RockKind>, count: u32 },
+    CoinsAtLeast(BigNum),
+    QuestsCompleted(u32),
+    RegionsVisited(u32),
+    AllRegionsVisited,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AchievementDef {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub condition: AchievementCondition,
+}
+
+/// Loaded from `data/*.achievements.ron`
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct AchievementBook {
+    pub achievements: Vec<AchievementDef>,
+}
+
+impl AchievementBook {
+    pub fn get(&self, id: &str) -> Option<&AchievementDef> {
+        self.achievements.iter().find(|def| def.id == id)
+    }
+}
+
+#[derive(Resource)]
+pub struct AchievementDefs {
+    pub book: Handle<AchievementBook>,
+    /// Every region of the map, visited or not
+    pub legend: Handle<MaskLegend>,
+}
+
+/// Unlocked achievements, persisted with the save
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Achievements {
+    pub unlocked: Vec<String>,
+}
+
+impl Achievements {
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.iter().any(|unlocked| unlocked == id)
+    }
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct AchievementUnlocked {
+    pub id: String,
+}
+
+/// Column of notifications in the corner, never takes pointer input
+#[derive(Component)]
+pub struct ToastStack;
+
+#[derive(Component)]
+struct Toast(Timer);
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<AchievementBook>::new(&[
+            "achievements.ron",
+        ]))
+        .init_resource::<Achievements>()
+        .add_observer(show_unlock_toast)
+        .add_systems(Startup, setup)
+        .add_systems(Update, (evaluate, update_toasts));
+    }
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(AchievementDefs {
+        book: asset_server.load("data/game.achievements.ron"),
+        legend: asset_server.load("data/map.legend.ron"),
+    });
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(20.),
+            left: Val::Percent(50.),
+            margin: UiRect::left(Val::Px(-200.)),
+            width: Val::Px(400.),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(8.),
+            ..default()
+        },
+        GlobalZIndex(20),
+        Pickable::IGNORE,
+        Name::new("Toasts"),
+        ToastStack,
+    ));
+}
+
+/// Game progress the achievement conditions are checked against
+#[derive(SystemParam)]
+struct Progress<'w> {
+    stats: Res<'w, Statistics>,
+    wallet: Res<'w, Wallet>,
+    log: Res<'w, QuestLog>,
+    legends: Res<'w, Assets<MaskLegend>>,
+}
+
+impl Progress<'_> {
+    fn is_changed(&self) -> bool {
+        self.stats.is_changed()
+            || self.wallet.is_changed()
+            || self.log.is_changed()
+            || self.legends.is_changed()
+    }
+
+    fn reached(&self, condition: &AchievementCondition, legend: &Handle<MaskLegend>) -> bool {
+        let stats = &self.stats;
+        match condition {
+            AchievementCondition::RocksBroken {
+                kind: Some(kind),
+                count,
+            } => stats.rocks_broken.get(kind).copied().unwrap_or(0) >= *count,
+            AchievementCondition::RocksBroken { kind: None, count } => {
+                stats.total_rocks_broken() >= *count
+            }
+            AchievementCondition::CoinsAtLeast(coins) => self.wallet.coins >= *coins,
+            AchievementCondition::QuestsCompleted(count) => {
+                self.log.completed.len() as u32 >= *count
+            }
+            AchievementCondition::RegionsVisited(count) => {
+                stats.regions_visited.len() as u32 >= *count
+            }
+            AchievementCondition::AllRegionsVisited => {
+                self.legends.get(legend).is_some_and(|legend| {
+                    !legend.regions.is_empty()
+                        && legend
+                            .regions
+                            .iter()
+                            .all(|region| stats.regions_visited.contains(&region.id))
+                })
+            }
+        }
+    }
+}
+
+fn evaluate(
+    mut commands: Commands,
+    mut achievements: ResMut<Achievements>,
+    defs: Res<AchievementDefs>,
+    books: Res<Assets<AchievementBook>>,
+    progress: Progress,
+) {
+    if !progress.is_changed() && !books.is_changed() {
+        return;
+    }
+    let Some(book) = books.get(&defs.book) else {
+        return;
+    };
+
+    for def in book.achievements.iter() {
+        if achievements.is_unlocked(&def.id) || !progress.reached(&def.condition, &defs.legend) {
+            continue;
+        }
+
+        achievements.unlocked.push(def.id.clone());
+        commands.trigger(AchievementUnlocked { id: def.id.clone() });
+    }
+}
+
+fn show_unlock_toast(
+    trigger: Trigger<AchievementUnlocked>,
+    mut commands: Commands,
+    defs: Res<AchievementDefs>,
+    books: Res<Assets<AchievementBook>>,
+    q_stack: Query<Entity, With<ToastStack>>,
+) {
+    let Some(def) = books
+        .get(&defs.book)
+        .and_then(|book| book.get(&trigger.event().id))
+    else {
+        return;
+    };
+    if let Ok(stack) = q_stack.single() {
+        spawn_toast(
+            &mut commands,
+            stack,
+            format!("Achievement unlocked: {}", def.title),
+            def.description.clone(),
+        );
+    }
+}
+
+/// Notification that fades away after [`TOAST_DURATION`]
+pub fn spawn_toast(commands: &mut Commands, stack: Entity, title: String, description: String) {
+    commands.spawn((
+        Node {
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Px(12.)),
+            ..default()
+        },
+        BackgroundColor(Color::linear_rgba(0.1, 0.08, 0.02, 0.9)),
+        BorderRadius::all(Val::Px(6.)),
+        Pickable::IGNORE,
+        Toast(Timer::new(TOAST_DURATION, TimerMode::Once)),
+        ChildOf(stack),
+        children![
+            (
+                Text::new(title),
+                TextFont {
+                    font_size: 20.,
+                    ..default()
+                },
+                TextColor(Color::linear_rgb(1., 0.8, 0.2)),
+                Pickable::IGNORE,
+            ),
+            (
+                Text::new(description),
+                TextFont {
+                    font_size: 16.,
+                    ..default()
+                },
+                Pickable::IGNORE,
+            ),
+        ],
+    ));
+}
+
+fn update_toasts(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut q_toasts: Query<(Entity, &mut Toast, &mut BackgroundColor)>,
+) {
+    for (entity, mut toast, mut background) in q_toasts.iter_mut() {
+        toast.0.tick(time.delta());
+        if toast.0.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        // Fade out during the last half second
+        let left = toast.0.remaining_secs();
+        if left < 0.5 {
+            background.0.set_alpha(0.9 * left / 0.5);
+        }
+    }
+}
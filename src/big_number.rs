@@ -0,0 +1,493 @@
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// Beyond this many orders of magnitude the smaller operand doesn't change an `f64` mantissa
+const MAX_PRECISION: i64 = 17;
+
+/// Short scale names before switching to generated `aa`, `ab`, ... suffixes
+const SHORT_SCALE: [&str; 5] = ["", "K", "M", "B", "T"];
+
+/**
+Number for clicker economies: `mantissa * 10^exponent`.
+
+The mantissa stays in `[1, 10)` (or `(-10, -1]`), zero is stored as `0e0`,
+so currency and damage can grow far past what `f64` or `u64` can hold.
+Serialized as a `"1.5e3"` string, plain numbers are accepted when reading.
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BigNum {
+    mantissa: f64,
+    exponent: i64,
+}
+
+impl BigNum {
+    pub const ZERO: BigNum = BigNum {
+        mantissa: 0.,
+        exponent: 0,
+    };
+
+    pub const ONE: BigNum = BigNum {
+        mantissa: 1.,
+        exponent: 0,
+    };
+
+    pub fn new(mantissa: f64, exponent: i64) -> Self {
+        if mantissa == 0. || !mantissa.is_finite() {
+            return Self::ZERO;
+        }
+
+        let shift = mantissa.abs().log10().floor() as i64;
+        let mut mantissa = mantissa / 10f64.powi(shift as i32);
+        let mut exponent = exponent.saturating_add(shift);
+
+        // log10 can be off by one right at powers of ten
+        if mantissa.abs() >= 10. {
+            mantissa /= 10.;
+            exponent += 1;
+        } else if mantissa.abs() < 1. {
+            mantissa *= 10.;
+            exponent -= 1;
+        }
+
+        Self { mantissa, exponent }
+    }
+
+    /// `10^value`, handy for exponential cost curves
+    pub fn from_log10(value: f64) -> Self {
+        let exponent = value.floor();
+        Self::new(10f64.powf(value - exponent), exponent as i64)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0.
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.mantissa < 0.
+    }
+
+    pub fn powi(&self, power: i32) -> Self {
+        if self.is_zero() {
+            return if power == 0 { Self::ONE } else { Self::ZERO };
+        }
+        // Exact while it fits, `100 * 2^n` cost curves shouldn't pick up log10 noise
+        let exact = self.to_f64().powi(power);
+        if exact.is_finite() && exact.abs() >= f64::MIN_POSITIVE {
+            return Self::from(exact);
+        }
+        let sign = if self.is_negative() && power % 2 != 0 {
+            -1.
+        } else {
+            1.
+        };
+        let magnitude = Self::from_log10(self.mantissa.abs().log10() * power as f64)
+            * Self::new(1., self.exponent.saturating_mul(power as i64));
+        magnitude * sign
+    }
+
+    /// Saturates to `f64::INFINITY` when the number doesn't fit
+    pub fn to_f64(self) -> f64 {
+        if self.exponent > f64::MAX_10_EXP as i64 {
+            return self.mantissa.signum() * f64::INFINITY;
+        }
+        if self.exponent < f64::MIN_10_EXP as i64 - MAX_PRECISION {
+            return 0.;
+        }
+        self.mantissa * 10f64.powi(self.exponent as i32)
+    }
+
+    /// Drops the fractional part, numbers that big have none anyway
+    pub fn floor(&self) -> Self {
+        if self.exponent >= MAX_PRECISION {
+            return *self;
+        }
+        Self::from(self.to_f64().floor())
+    }
+
+    /// `1.23K`, `45.6aa`, falls back to scientific once the suffixes run out
+    pub fn format_short(&self) -> String {
+        let (mut group, value) = if self.exponent < 3 {
+            (0, self.to_f64())
+        } else {
+            let group = self.exponent.div_euclid(3);
+            let value = self.mantissa * 10f64.powi((self.exponent - group * 3) as i32);
+            (group, value)
+        };
+        // Rounded before picking the suffix, 999.999 would print as 1000 otherwise
+        let mut value = (value * 100.).round() / 100.;
+        if value.abs() >= 1000. {
+            value /= 1000.;
+            group += 1;
+        }
+
+        match short_scale_suffix(group) {
+            Some(suffix) => format!("{}{}", trim_decimals(format!("{:.2}", value)), suffix),
+            None => self.format_scientific(),
+        }
+    }
+
+    /// `1.23e45`
+    pub fn format_scientific(&self) -> String {
+        if self.exponent < 3 {
+            return self.format_short();
+        }
+        let mut mantissa = (self.mantissa * 100.).round() / 100.;
+        let mut exponent = self.exponent;
+        // 9.996e5 would print as 10e5
+        if mantissa.abs() >= 10. {
+            mantissa /= 10.;
+            exponent += 1;
+        }
+        format!("{}e{}", trim_decimals(format!("{:.2}", mantissa)), exponent)
+    }
+}
+
+fn trim_decimals(text: String) -> String {
+    if !text.contains('.') {
+        return text;
+    }
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn short_scale_suffix(group: i64) -> Option<String> {
+    if group < 0 {
+        return None;
+    }
+    if let Some(suffix) = SHORT_SCALE.get(group as usize) {
+        return Some(suffix.to_string());
+    }
+
+    // aa, ab, ..., az, ba, ..., zz
+    let index = group - SHORT_SCALE.len() as i64;
+    if index >= 26 * 26 {
+        return None;
+    }
+    let first = (b'a' + (index / 26) as u8) as char;
+    let second = (b'a' + (index % 26) as u8) as char;
+    Some(format!("{}{}", first, second))
+}
+
+impl fmt::Display for BigNum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format_short())
+    }
+}
+
+impl From<f64> for BigNum {
+    fn from(value: f64) -> Self {
+        Self::new(value, 0)
+    }
+}
+
+impl From<f32> for BigNum {
+    fn from(value: f32) -> Self {
+        Self::new(value as f64, 0)
+    }
+}
+
+impl From<u64> for BigNum {
+    fn from(value: u64) -> Self {
+        Self::new(value as f64, 0)
+    }
+}
+
+impl From<u32> for BigNum {
+    fn from(value: u32) -> Self {
+        Self::new(value as f64, 0)
+    }
+}
+
+impl PartialOrd for BigNum {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let sign = self.mantissa.signum() as i8 * !self.is_zero() as i8;
+        let other_sign = other.mantissa.signum() as i8 * !other.is_zero() as i8;
+        if sign != other_sign || sign == 0 {
+            return Some(sign.cmp(&other_sign));
+        }
+
+        let magnitude = self
+            .exponent
+            .cmp(&other.exponent)
+            .then(self.mantissa.abs().total_cmp(&other.mantissa.abs()));
+        Some(if sign > 0 {
+            magnitude
+        } else {
+            magnitude.reverse()
+        })
+    }
+}
+
+impl Neg for BigNum {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self {
+            mantissa: -self.mantissa,
+            ..self
+        }
+    }
+}
+
+impl Add for BigNum {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        if self.is_zero() {
+            return other;
+        }
+        if other.is_zero() {
+            return self;
+        }
+
+        let diff = self.exponent - other.exponent;
+        if diff > MAX_PRECISION {
+            return self;
+        }
+        if diff < -MAX_PRECISION {
+            return other;
+        }
+        Self::new(
+            self.mantissa + other.mantissa * 10f64.powi(-diff as i32),
+            self.exponent,
+        )
+    }
+}
+
+impl Sub for BigNum {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Mul for BigNum {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.mantissa * other.mantissa,
+            self.exponent.saturating_add(other.exponent),
+        )
+    }
+}
+
+impl Mul<f64> for BigNum {
+    type Output = Self;
+    /// Goes through [`BigNum::from`] so a huge factor can't overflow the mantissa,
+    /// infinite and NaN factors give zero like in [`BigNum::new`]
+    fn mul(self, other: f64) -> Self {
+        self * Self::from(other)
+    }
+}
+
+impl Div for BigNum {
+    type Output = Self;
+    /// Division by zero gives zero rather than poisoning the wallet with infinities
+    fn div(self, other: Self) -> Self {
+        if other.is_zero() {
+            return Self::ZERO;
+        }
+        Self::new(
+            self.mantissa / other.mantissa,
+            self.exponent.saturating_sub(other.exponent),
+        )
+    }
+}
+
+impl AddAssign for BigNum {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for BigNum {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign<f64> for BigNum {
+    fn mul_assign(&mut self, other: f64) {
+        *self = *self * other;
+    }
+}
+
+impl Sum for BigNum {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, |acc, value| acc + value)
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseBigNumError;
+
+impl fmt::Display for ParseBigNumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("expected a number like `1500` or `1.5e3`")
+    }
+}
+
+impl std::error::Error for ParseBigNumError {}
+
+impl FromStr for BigNum {
+    type Err = ParseBigNumError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (
+                mantissa.parse::<f64>().map_err(|_| ParseBigNumError)?,
+                exponent.parse::<i64>().map_err(|_| ParseBigNumError)?,
+            ),
+            None => (text.parse::<f64>().map_err(|_| ParseBigNumError)?, 0),
+        };
+        Ok(Self::new(mantissa, exponent))
+    }
+}
+
+impl Serialize for BigNum {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{}e{}", self.mantissa, self.exponent))
+    }
+}
+
+struct BigNumVisitor;
+
+impl Visitor<'_> for BigNumVisitor {
+    type Value = BigNum;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a number or a string like \"1.5e3\"")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<BigNum, E> {
+        Ok(BigNum::from(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<BigNum, E> {
+        Ok(BigNum::from(value as f64))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<BigNum, E> {
+        Ok(BigNum::from(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<BigNum, E> {
+        value.parse().map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for BigNum {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(BigNumVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: BigNum, expected: f64) {
+        let value = value.to_f64();
+        assert!(
+            (value - expected).abs() <= expected.abs() * 1e-12,
+            "{} != {}",
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn add_across_exponents() {
+        assert_close(BigNum::from(1000u64) + BigNum::ONE, 1001.);
+        assert_close(BigNum::from(500u64) + BigNum::from(500u64), 1000.);
+        assert_eq!(BigNum::new(1., 20) + BigNum::ONE, BigNum::new(1., 20));
+        assert_eq!(BigNum::ONE + BigNum::new(1., 20), BigNum::new(1., 20));
+    }
+
+    #[test]
+    fn sub_down_to_zero_and_below() {
+        assert_close(BigNum::from(1000u64) - BigNum::ONE, 999.);
+        assert!((BigNum::ONE - BigNum::ONE).is_zero());
+        let negative = BigNum::ONE - BigNum::from(1000u64);
+        assert!(negative.is_negative());
+        assert_close(negative, -999.);
+    }
+
+    #[test]
+    fn negatives_order_below_positives() {
+        let small = BigNum::from(-5.);
+        let big = BigNum::from(-50.);
+        assert!(big < small);
+        assert!(small < BigNum::ZERO);
+        assert!(BigNum::ZERO < BigNum::from(3u64));
+        assert_eq!(-BigNum::from(3u64), BigNum::from(-3.));
+    }
+
+    #[test]
+    fn huge_f64_factor_doesnt_overflow() {
+        let value = BigNum::new(9., 0) * 1e308;
+        assert!(value > BigNum::new(8., 308));
+    }
+
+    #[test]
+    fn non_finite_factor_gives_zero() {
+        assert!((BigNum::from(5u64) * f64::INFINITY).is_zero());
+        assert!((BigNum::from(5u64) * f64::NEG_INFINITY).is_zero());
+        assert!((BigNum::from(5u64) * f64::NAN).is_zero());
+    }
+
+    #[test]
+    fn powi_is_exact_in_f64_range() {
+        for n in 0..40 {
+            let cost = BigNum::from(100u64) * BigNum::from(2u64).powi(n);
+            assert_eq!(cost, BigNum::from(100u64) * BigNum::from(2f64.powi(n)));
+        }
+        assert_eq!(BigNum::from(10u64).powi(3), BigNum::from(1000u64));
+        assert_eq!(BigNum::from(-2.).powi(3), BigNum::from(-8.));
+        assert_eq!(BigNum::new(1., 200).powi(2), BigNum::new(1., 400));
+        assert_close(BigNum::from(2u64).powi(-2), 0.25);
+    }
+
+    #[test]
+    fn format_short_boundaries() {
+        assert_eq!(BigNum::from(999u64).format_short(), "999");
+        assert_eq!(BigNum::from(999.999).format_short(), "1K");
+        assert_eq!(BigNum::from(999.994).format_short(), "999.99");
+        assert_eq!(BigNum::from(1000u64).format_short(), "1K");
+        assert_eq!(BigNum::from(1500u64).format_short(), "1.5K");
+        assert_eq!(BigNum::from(999_999u64).format_short(), "1M");
+        assert_eq!(BigNum::new(1., 15).format_short(), "1aa");
+        assert_eq!(BigNum::from(-2500.).format_short(), "-2.5K");
+    }
+
+    #[test]
+    fn format_scientific_carries() {
+        assert_eq!(BigNum::new(9.996, 5).format_scientific(), "1e6");
+        assert_eq!(BigNum::new(1.234, 45).format_scientific(), "1.23e45");
+        assert_eq!(BigNum::new(-9.999, 7).format_scientific(), "-1e8");
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        for value in [
+            BigNum::ZERO,
+            BigNum::from(1500u64),
+            BigNum::new(-4.25, 3),
+            BigNum::new(7.5, 400),
+        ] {
+            let text = ron::to_string(&value).unwrap();
+            assert_eq!(ron::from_str::<BigNum>(&text).unwrap(), value);
+        }
+        assert_eq!(
+            ron::from_str::<BigNum>("1500").unwrap(),
+            BigNum::from(1500u64)
+        );
+        assert_eq!(
+            ron::from_str::<BigNum>("\"1.5e3\"").unwrap(),
+            BigNum::from(1500u64)
+        );
+    }
+}
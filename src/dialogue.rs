@@ -0,0 +1,505 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use crate::big_number::BigNum;
+use crate::economy::{Inventory, Wallet};
+use crate::quest::{QuestLog, QuestStatus};
+use crate::ron_asset::RonAssetPlugin;
+use crate::states::GameState;
+
+const CHARS_PER_SECOND: f32 = 40.;
+
+pub struct DialoguePlugin;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Character {
+    pub id: String,
+    pub name: String,
+    pub portrait: String,
+    /// Node the conversation starts from
+    pub start: String,
+}
+
+/// Checks against the game state, all of them have to pass
+#[derive(Deserialize, Debug, Clone)]
+pub enum Condition {
+    CoinsAtLeast(BigNum),
+    HasItem(String, u32),
+    QuestAvailable(String),
+    QuestActive(String),
+    QuestCompleted(String),
+    FlagNotSet(String),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub enum Effect {
+    GiveCoins(BigNum),
+    SpendCoins(BigNum),
+    GiveItem(String, u32),
+    StartQuest(String),
+    SetFlag(String),
+}
+
+/// Jumps straight to `next` when entering a node, if all conditions pass
+#[derive(Deserialize, Debug, Clone)]
+pub struct Branch {
+    pub conditions: Vec<Condition>,
+    pub next: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Choice {
+    pub text: String,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+    /// `None` ends the conversation
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DialogueNode {
+    pub id: String,
+    pub speaker: String,
+    pub text: String,
+    #[serde(default)]
+    pub branches: Vec<Branch>,
+    #[serde(default)]
+    pub choices: Vec<Choice>,
+}
+
+/// Characters and dialogue trees, loaded from `data/*.dialogue.ron`
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct DialogueBook {
+    pub characters: Vec<Character>,
+    pub nodes: Vec<DialogueNode>,
+}
+
+impl DialogueBook {
+    pub fn character(&self, id: &str) -> Option<&Character> {
+        self.characters.iter().find(|character| character.id == id)
+    }
+
+    pub fn node(&self, id: &str) -> Option<&DialogueNode> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+}
+
+#[derive(Resource)]
+pub struct Dialogues {
+    pub book: Handle<DialogueBook>,
+}
+
+/// Starts talking to the character with the given id
+#[derive(Event, Debug, Clone)]
+pub struct StartDialogue {
+    pub character: String,
+}
+
+/// Flags set by dialogue so one-off choices stay one-off, persisted with the save
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone)]
+pub struct DialogueFlags(pub BTreeSet<String>);
+
+#[derive(Resource, Debug)]
+struct ActiveDialogue {
+    node: String,
+}
+
+#[derive(Component)]
+struct DialogueBox;
+
+#[derive(Component)]
+struct DialoguePortrait;
+
+#[derive(Component)]
+struct DialogueSpeaker;
+
+#[derive(Component)]
+struct DialogueChoices;
+
+/// Index into the current node's choices, `usize::MAX` for the implicit goodbye
+#[derive(Component)]
+struct ChoiceButton(usize);
+
+#[derive(Component, Default)]
+struct Typewriter {
+    full: String,
+    shown: f32,
+    choices_spawned: bool,
+}
+
+impl Typewriter {
+    fn finished(&self) -> bool {
+        self.shown as usize >= self.full.chars().count()
+    }
+}
+
+/// Game state dialogue conditions read and effects write to
+#[derive(SystemParam)]
+struct DialogueContext<'w> {
+    wallet: ResMut<'w, Wallet>,
+    inventory: ResMut<'w, Inventory>,
+    log: ResMut<'w, QuestLog>,
+    flags: ResMut<'w, DialogueFlags>,
+}
+
+/// The loaded dialogue book and the node currently on screen
+#[derive(SystemParam)]
+struct CurrentDialogue<'w> {
+    active: Res<'w, ActiveDialogue>,
+    dialogues: Res<'w, Dialogues>,
+    books: Res<'w, Assets<DialogueBook>>,
+}
+
+impl CurrentDialogue<'_> {
+    fn book(&self) -> Option<&DialogueBook> {
+        self.books.get(&self.dialogues.book)
+    }
+
+    fn node(&self) -> Option<&DialogueNode> {
+        self.book().and_then(|book| book.node(&self.active.node))
+    }
+}
+
+impl DialogueContext<'_> {
+    fn check(&self, conditions: &[Condition]) -> bool {
+        conditions.iter().all(|condition| match condition {
+            Condition::CoinsAtLeast(coins) => self.wallet.coins >= *coins,
+            Condition::HasItem(item, count) => self.inventory.count(item) >= *count,
+            Condition::QuestAvailable(id) => self.log.status(id) == QuestStatus::Available,
+            Condition::QuestActive(id) => matches!(self.log.status(id), QuestStatus::Active(_)),
+            Condition::QuestCompleted(id) => self.log.status(id) == QuestStatus::Completed,
+            Condition::FlagNotSet(flag) => !self.flags.0.contains(flag),
+        })
+    }
+
+    fn apply(&mut self, effects: &[Effect]) {
+        for effect in effects {
+            match effect {
+                Effect::GiveCoins(coins) => self.wallet.earn(*coins),
+                Effect::SpendCoins(coins) => {
+                    self.wallet.spend(*coins);
+                }
+                Effect::GiveItem(item, count) => self.inventory.add(item, *count),
+                Effect::StartQuest(id) => {
+                    self.log.accept(id);
+                }
+                Effect::SetFlag(flag) => {
+                    self.flags.0.insert(flag.clone());
+                }
+            }
+        }
+    }
+
+    /// Follows branches until reaching a node that is actually shown
+    fn resolve<'a>(&self, book: &'a DialogueBook, id: &str) -> Option<&'a DialogueNode> {
+        let mut node = book.node(id)?;
+        // Guard against branch cycles in the data
+        for _ in 0..book.nodes.len() {
+            match node
+                .branches
+                .iter()
+                .find(|branch| self.check(&branch.conditions))
+            {
+                Some(branch) => node = book.node(&branch.next)?,
+                None => return Some(node),
+            }
+        }
+        warn!("Dialogue branches starting at {} loop forever", id);
+        None
+    }
+}
+
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<DialogueBook>::new(&["dialogue.ron"]))
+            .init_resource::<DialogueFlags>()
+            .add_systems(Startup, load_dialogues)
+            .add_observer(start_dialogue)
+            .add_systems(OnExit(GameState::Tavern), close_dialogue)
+            .add_systems(
+                Update,
+                (
+                    present_node.run_if(resource_exists_and_changed::<ActiveDialogue>),
+                    typewriter,
+                )
+                    .chain()
+                    .run_if(resource_exists::<ActiveDialogue>),
+            );
+    }
+}
+
+fn load_dialogues(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Dialogues {
+        book: asset_server.load("data/tavern.dialogue.ron"),
+    });
+}
+
+fn start_dialogue(
+    trigger: Trigger<StartDialogue>,
+    mut commands: Commands,
+    dialogues: Res<Dialogues>,
+    books: Res<Assets<DialogueBook>>,
+    context: DialogueContext,
+    q_box: Query<Entity, With<DialogueBox>>,
+) {
+    let Some(book) = books.get(&dialogues.book) else {
+        return;
+    };
+    let Some(character) = book.character(&trigger.event().character) else {
+        warn!("Unknown character {}", trigger.event().character);
+        return;
+    };
+    let Some(node) = context.resolve(book, &character.start) else {
+        return;
+    };
+
+    commands.insert_resource(ActiveDialogue {
+        node: node.id.clone(),
+    });
+
+    if !q_box.is_empty() {
+        return;
+    }
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(10.),
+                right: Val::Percent(10.),
+                bottom: Val::Px(30.),
+                min_height: Val::Px(220.),
+                padding: UiRect::all(Val::Px(16.)),
+                column_gap: Val::Px(20.),
+                ..default()
+            },
+            BackgroundColor(Color::linear_rgba(0.02, 0.02, 0.04, 0.92)),
+            BorderRadius::all(Val::Px(8.)),
+            GlobalZIndex(10),
+            Name::new("Dialogue"),
+            DialogueBox,
+            children![
+                (
+                    Node {
+                        width: Val::Px(180.),
+                        height: Val::Px(180.),
+                        ..default()
+                    },
+                    ImageNode::default(),
+                    Pickable::IGNORE,
+                    DialoguePortrait,
+                ),
+                (
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        flex_grow: 1.,
+                        row_gap: Val::Px(10.),
+                        ..default()
+                    },
+                    Pickable::IGNORE,
+                    children![
+                        (
+                            Text::default(),
+                            TextFont {
+                                font_size: 24.,
+                                ..default()
+                            },
+                            TextColor(Color::linear_rgb(1., 0.8, 0.4)),
+                            Pickable::IGNORE,
+                            DialogueSpeaker,
+                        ),
+                        (
+                            Text::default(),
+                            TextFont {
+                                font_size: 20.,
+                                ..default()
+                            },
+                            Pickable::IGNORE,
+                            Typewriter::default(),
+                        ),
+                        (
+                            Node {
+                                flex_direction: FlexDirection::Column,
+                                row_gap: Val::Px(6.),
+                                ..default()
+                            },
+                            DialogueChoices,
+                        ),
+                    ],
+                ),
+            ],
+        ))
+        .observe(
+            |_trigger: Trigger<Pointer<Click>>, mut q_typewriter: Query<&mut Typewriter>| {
+                // Clicking the box skips the typing animation
+                for mut typewriter in q_typewriter.iter_mut() {
+                    typewriter.shown = typewriter.full.chars().count() as f32;
+                }
+            },
+        );
+}
+
+fn present_node(
+    mut commands: Commands,
+    current: CurrentDialogue,
+    asset_server: Res<AssetServer>,
+    mut q_speaker: Query<&mut Text, With<DialogueSpeaker>>,
+    mut q_portrait: Query<&mut ImageNode, With<DialoguePortrait>>,
+    mut q_typewriter: Query<&mut Typewriter>,
+    q_choices: Query<Entity, With<DialogueChoices>>,
+) {
+    let Some(book) = current.book() else {
+        return;
+    };
+    let Some(node) = current.node() else {
+        return;
+    };
+    let Some(character) = book.character(&node.speaker) else {
+        warn!("Unknown speaker {} in node {}", node.speaker, node.id);
+        return;
+    };
+
+    for mut speaker in q_speaker.iter_mut() {
+        speaker.0 = character.name.clone();
+    }
+    for mut portrait in q_portrait.iter_mut() {
+        portrait.image = asset_server.load(&character.portrait);
+    }
+    for mut typewriter in q_typewriter.iter_mut() {
+        *typewriter = Typewriter {
+            full: node.text.clone(),
+            ..default()
+        };
+    }
+    for choices in q_choices.iter() {
+        commands.entity(choices).despawn_related::<Children>();
+    }
+}
+
+fn typewriter(
+    time: Res<Time>,
+    mut commands: Commands,
+    current: CurrentDialogue,
+    context: DialogueContext,
+    mut q_typewriter: Query<(&mut Text, &mut Typewriter)>,
+    q_choices: Query<Entity, With<DialogueChoices>>,
+) {
+    for (mut text, mut typewriter) in q_typewriter.iter_mut() {
+        if !typewriter.finished() {
+            typewriter.shown += CHARS_PER_SECOND * time.delta_secs();
+            text.0 = typewriter
+                .full
+                .chars()
+                .take(typewriter.shown as usize)
+                .collect();
+            continue;
+        }
+
+        if typewriter.choices_spawned {
+            continue;
+        }
+        typewriter.choices_spawned = true;
+        text.0 = typewriter.full.clone();
+
+        let Some(node) = current.node() else {
+            continue;
+        };
+        let Ok(container) = q_choices.single() else {
+            continue;
+        };
+
+        let mut visible: Vec<(usize, &Choice)> = node
+            .choices
+            .iter()
+            .enumerate()
+            .filter(|(_, choice)| context.check(&choice.conditions))
+            .collect();
+
+        // Nodes without options still need a way out
+        let goodbye = Choice {
+            text: "Goodbye".to_string(),
+            conditions: Vec::new(),
+            effects: Vec::new(),
+            next: None,
+        };
+        if visible.is_empty() {
+            visible.push((usize::MAX, &goodbye));
+        }
+
+        for (index, choice) in visible {
+            commands
+                .spawn((
+                    Button,
+                    Node {
+                        padding: UiRect::axes(Val::Px(12.), Val::Px(4.)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::linear_rgba(1., 1., 1., 0.08)),
+                    ChoiceButton(index),
+                    ChildOf(container),
+                    children![(
+                        Text::new(format!("> {}", choice.text)),
+                        TextFont {
+                            font_size: 18.,
+                            ..default()
+                        },
+                        Pickable::IGNORE,
+                    )],
+                ))
+                .observe(choose);
+        }
+    }
+}
+
+/// Applies the effects of the chosen option and moves on to the next node
+fn choose(
+    trigger: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    current: CurrentDialogue,
+    mut context: DialogueContext,
+    q_button: Query<&ChoiceButton>,
+    q_box: Query<Entity, With<DialogueBox>>,
+) {
+    let Ok(ChoiceButton(index)) = q_button.get(trigger.target()) else {
+        return;
+    };
+    let Some(book) = current.book() else {
+        return;
+    };
+    let choice = current.node().and_then(|node| node.choices.get(*index));
+
+    let next = match choice {
+        Some(choice) => {
+            context.apply(&choice.effects);
+            choice
+                .next
+                .as_ref()
+                .and_then(|next| context.resolve(book, next))
+        }
+        None => None,
+    };
+
+    match next {
+        Some(node) => commands.insert_resource(ActiveDialogue {
+            node: node.id.clone(),
+        }),
+        None => {
+            commands.remove_resource::<ActiveDialogue>();
+            for entity in q_box.iter() {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+fn close_dialogue(mut commands: Commands, q_box: Query<Entity, With<DialogueBox>>) {
+    commands.remove_resource::<ActiveDialogue>();
+    for entity in q_box.iter() {
+        commands.entity(entity).despawn();
+    }
+}
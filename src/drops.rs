@@ -0,0 +1,151 @@
+use bevy::asset::AssetPath;
+use bevy::pbr::OpaqueRendererMethod;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::materials::{CoinMaterial, MyExtendedMaterial, ToonMaterial};
+use crate::mine_plugin::RockKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GemKind {
+    Ruby,
+    Sapphire,
+    Emerald,
+}
+
+impl GemKind {
+    pub const ALL: [GemKind; 3] = [GemKind::Ruby, GemKind::Sapphire, GemKind::Emerald];
+
+    /// Loot item ids from `data/mines.mines.ron`
+    pub fn from_item(item: &str) -> Option<Self> {
+        match item {
+            "ruby" => Some(GemKind::Ruby),
+            "sapphire" => Some(GemKind::Sapphire),
+            "emerald" => Some(GemKind::Emerald),
+            _ => None,
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            GemKind::Ruby => Color::linear_rgb(0.85, 0.05, 0.1),
+            GemKind::Sapphire => Color::linear_rgb(0.1, 0.2, 0.9),
+            GemKind::Emerald => Color::linear_rgb(0.05, 0.75, 0.25),
+        }
+    }
+}
+
+/// Anything that flies out of a broken rock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DropKind {
+    /// Coins take the colour of the ore they come from
+    Coin(RockKind),
+    Gem(GemKind),
+}
+
+/// Coins keep the metallic look, gems are cel shaded
+#[derive(Debug, Clone)]
+pub enum DropMaterial {
+    Coin(Handle<CoinMaterial>),
+    Toon(Handle<ToonMaterial>),
+}
+
+#[derive(Debug, Clone)]
+pub struct DropVisual {
+    pub mesh: Handle<Mesh>,
+    pub material: DropMaterial,
+}
+
+impl DropVisual {
+    /// Adds the mesh and material to a drop entity
+    pub fn apply(&self, entity: &mut EntityCommands) {
+        entity.insert(Mesh3d(self.mesh.clone()));
+        match &self.material {
+            DropMaterial::Coin(material) => entity.insert(MeshMaterial3d(material.clone())),
+            DropMaterial::Toon(material) => entity.insert(MeshMaterial3d(material.clone())),
+        };
+    }
+}
+
+/**
+Mesh and material of every [`DropKind`].
+
+Built once when the mine loads for the first time, drops only clone the
+handles so all coins of an ore share the same material.
+*/
+#[derive(Resource, Debug)]
+pub struct DropVisuals(HashMap<DropKind, DropVisual>);
+
+fn mesh_coin_path() -> AssetPath<'static> {
+    GltfAssetLabel::Primitive {
+        mesh: 0,
+        primitive: 0,
+    }
+    .from_asset("private/coin.glb")
+}
+
+impl DropVisuals {
+    pub fn new(
+        asset_server: &AssetServer,
+        meshes: &mut Assets<Mesh>,
+        coin_materials: &mut Assets<CoinMaterial>,
+        toon_materials: &mut Assets<ToonMaterial>,
+    ) -> Self {
+        let mut visuals = HashMap::new();
+
+        let coin: Handle<Mesh> = asset_server.load(mesh_coin_path());
+        for kind in [RockKind::Copper, RockKind::Silver, RockKind::Gold] {
+            let base_color = match kind {
+                RockKind::Copper => Color::linear_rgb(0.95, 0.4, 0.15),
+                RockKind::Silver => Color::linear_rgb(0.85, 0.87, 0.9),
+                RockKind::Gold => Color::linear_rgb(1.0, 0.75, 0.1),
+            };
+            let material = coin_materials.add(CoinMaterial {
+                base: StandardMaterial {
+                    base_color,
+                    perceptual_roughness: 0.25,
+                    metallic: 0.96,
+                    reflectance: 0.96,
+
+                    opaque_render_method: OpaqueRendererMethod::Auto,
+                    ..Default::default()
+                },
+                extension: MyExtendedMaterial::default(),
+            });
+            visuals.insert(
+                DropKind::Coin(kind),
+                DropVisual {
+                    mesh: coin.clone(),
+                    material: DropMaterial::Coin(material),
+                },
+            );
+        }
+
+        // Flat shaded icosahedron, reads as a cut stone
+        let gem = meshes.add(
+            Sphere::new(0.12)
+                .mesh()
+                .ico(0)
+                .expect("icosphere without subdivisions")
+                .with_duplicated_vertices()
+                .with_computed_flat_normals(),
+        );
+        for kind in GemKind::ALL {
+            visuals.insert(
+                DropKind::Gem(kind),
+                DropVisual {
+                    mesh: gem.clone(),
+                    material: DropMaterial::Toon(
+                        toon_materials.add(ToonMaterial::new(kind.color())),
+                    ),
+                },
+            );
+        }
+
+        DropVisuals(visuals)
+    }
+
+    pub fn get(&self, kind: DropKind) -> Option<&DropVisual> {
+        self.0.get(&kind)
+    }
+}
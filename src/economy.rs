@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::big_number::BigNum;
+
+pub struct EconomyPlugin;
+
+#[derive(Resource, Default, Debug, Clone, Serialize, Deserialize)]
+pub struct Wallet {
+    pub coins: BigNum,
+    /// Everything earned so far, spending doesn't lower it
+    #[serde(default)]
+    pub earned: BigNum,
+}
+
+impl Wallet {
+    pub fn earn(&mut self, amount: BigNum) {
+        self.coins += amount;
+        self.earned += amount;
+    }
+
+    /// Returns `false` and leaves the wallet untouched when there is not enough coins
+    pub fn spend(&mut self, amount: BigNum) -> bool {
+        if self.coins < amount {
+            return false;
+        }
+        self.coins -= amount;
+        true
+    }
+}
+
+/// Items the player carries around, keyed by item id (`"silver_bar"`, ...)
+#[derive(Resource, Default, Debug, Clone, Serialize, Deserialize)]
+pub struct Inventory {
+    pub items: HashMap<String, u32>,
+}
+
+impl Inventory {
+    pub fn count(&self, item: &str) -> u32 {
+        self.items.get(item).copied().unwrap_or(0)
+    }
+
+    pub fn add(&mut self, item: &str, count: u32) {
+        *self.items.entry(item.to_string()).or_insert(0) += count;
+    }
+
+    /// Returns `false` and leaves the inventory untouched when there is not enough items
+    pub fn take(&mut self, item: &str, count: u32) -> bool {
+        if self.count(item) < count {
+            return false;
+        }
+        *self.items.entry(item.to_string()).or_insert(0) -= count;
+        true
+    }
+}
+
+/// Bought with coins in the Tavern, lost on prestige
+#[derive(Resource, Default, Debug, Clone, Serialize, Deserialize)]
+pub struct Upgrades {
+    pub pickaxe: u32,
+    #[serde(default)]
+    pub boots: u32,
+}
+
+impl Upgrades {
+    pub fn pickaxe_cost(&self) -> BigNum {
+        BigNum::from(100u64) * BigNum::from(2u64).powi(self.pickaxe as i32)
+    }
+
+    pub fn damage_multiplier(&self) -> f64 {
+        1. + 0.25 * self.pickaxe as f64
+    }
+
+    pub fn boots_cost(&self) -> BigNum {
+        BigNum::from(250u64) * BigNum::from(3u64).powi(self.boots as i32)
+    }
+
+    /// Applies to travel on the map
+    pub fn travel_speed_multiplier(&self) -> f32 {
+        1. + 0.5 * self.boots as f32
+    }
+}
+
+impl Plugin for EconomyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Wallet>()
+            .init_resource::<Inventory>()
+            .init_resource::<Upgrades>();
+    }
+}
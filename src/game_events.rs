@@ -0,0 +1,36 @@
+use bevy::prelude::*;
+
+use crate::big_number::BigNum;
+use crate::mine_plugin::RockKind;
+
+/**
+Gameplay events other subsystems (quests, ...) listen to.
+
+They are triggered with `commands.trigger(..)` so listeners are plain observers
+and work regardless of the current `GameState`.
+*/
+#[derive(Event, Debug, Clone)]
+pub struct RockHit {
+    pub damage: BigNum,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct RockDestroyed {
+    pub kind: RockKind,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct ItemCrafted {
+    pub item: String,
+    pub count: u32,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct RegionVisited {
+    pub region: String,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct RegionUnlocked {
+    pub region: String,
+}
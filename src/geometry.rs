@@ -0,0 +1,397 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+
+/**
+Traces the outlines of the `true` cells of a `width * height` row-major grid.
+
+Classic marching squares: every 2x2 block of samples emits segments between
+edge midpoints, segments are then chained into closed loops. The grid is
+padded with `false` so loops touching the border still close. Points are in
+grid units, `(0, 0)` being the first sample.
+*/
+pub fn marching_squares(grid: &[bool], width: usize, height: usize) -> Vec<Vec<Vec2>> {
+    let sample = |x: i32, y: i32| -> bool {
+        if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+            return false;
+        }
+        grid[y as usize * width + x as usize]
+    };
+
+    // Midpoints are kept in doubled integer coordinates so they hash exactly
+    let mut segments: Vec<(IVec2, IVec2)> = Vec::new();
+    for y in -1..height as i32 {
+        for x in -1..width as i32 {
+            let case = (sample(x, y) as u8) << 3
+                | (sample(x + 1, y) as u8) << 2
+                | (sample(x + 1, y + 1) as u8) << 1
+                | sample(x, y + 1) as u8;
+
+            let top = IVec2::new(2 * x + 1, 2 * y);
+            let right = IVec2::new(2 * x + 2, 2 * y + 1);
+            let bottom = IVec2::new(2 * x + 1, 2 * y + 2);
+            let left = IVec2::new(2 * x, 2 * y + 1);
+
+            match case {
+                1 | 14 => segments.push((left, bottom)),
+                2 | 13 => segments.push((bottom, right)),
+                3 | 12 => segments.push((left, right)),
+                4 | 11 => segments.push((top, right)),
+                6 | 9 => segments.push((top, bottom)),
+                7 | 8 => segments.push((left, top)),
+                5 => {
+                    segments.push((left, top));
+                    segments.push((bottom, right));
+                }
+                10 => {
+                    segments.push((top, right));
+                    segments.push((left, bottom));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Every midpoint is shared by exactly two segments
+    let mut neighbours: HashMap<IVec2, Vec<IVec2>> = HashMap::new();
+    for (a, b) in segments.iter() {
+        neighbours.entry(*a).or_default().push(*b);
+        neighbours.entry(*b).or_default().push(*a);
+    }
+
+    let mut loops = Vec::new();
+    let mut visited: HashSet<IVec2> = HashSet::new();
+    for (start, _) in segments.iter() {
+        if visited.contains(start) {
+            continue;
+        }
+
+        let mut contour = Vec::new();
+        let mut previous = *start;
+        let mut current = *start;
+        loop {
+            visited.insert(current);
+            contour.push(current.as_vec2() / 2.);
+
+            let next = neighbours[&current]
+                .iter()
+                .find(|next| **next != previous && !visited.contains(*next));
+            match next {
+                Some(next) => {
+                    previous = current;
+                    current = *next;
+                }
+                None => break,
+            }
+        }
+
+        if contour.len() >= 3 {
+            loops.push(contour);
+        }
+    }
+
+    loops
+}
+
+pub fn signed_area(polygon: &[Vec2]) -> f32 {
+    let mut area = 0.;
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        area += a.perp_dot(b);
+    }
+    area / 2.
+}
+
+/// Even-odd rule
+pub fn contains_point(polygon: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+fn distance_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let length = ab.length_squared();
+    if length == 0. {
+        return point.distance(a);
+    }
+    let t = ((point - a).dot(ab) / length).clamp(0., 1.);
+    point.distance(a + ab * t)
+}
+
+fn douglas_peucker(points: &[Vec2], epsilon: f32, out: &mut Vec<Vec2>) {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let farthest = points[1..points.len() - 1]
+        .iter()
+        .enumerate()
+        .map(|(i, point)| (i + 1, distance_to_segment(*point, first, last)))
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+
+    match farthest {
+        Some((index, distance)) if distance > epsilon => {
+            douglas_peucker(&points[..=index], epsilon, out);
+            douglas_peucker(&points[index..], epsilon, out);
+        }
+        _ => out.push(first),
+    }
+}
+
+/// Douglas-Peucker on a closed loop, drops points closer than `epsilon` to the outline
+pub fn simplify(polygon: &[Vec2], epsilon: f32) -> Vec<Vec2> {
+    if polygon.len() < 4 {
+        return polygon.to_vec();
+    }
+
+    // Split the loop at the point farthest from the start, both halves keep their ends
+    let split = polygon
+        .iter()
+        .enumerate()
+        .max_by(|a, b| {
+            a.1.distance_squared(polygon[0])
+                .total_cmp(&b.1.distance_squared(polygon[0]))
+        })
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+        .max(1);
+
+    let mut closed = polygon.to_vec();
+    closed.push(polygon[0]);
+
+    let mut out = Vec::new();
+    douglas_peucker(&closed[..=split], epsilon, &mut out);
+    douglas_peucker(&closed[split..], epsilon, &mut out);
+
+    // A big epsilon flattens the loop into a line, keep its widest triangle instead
+    if out.len() < 3 {
+        let (first, far) = (polygon[0], polygon[split]);
+        let third = polygon
+            .iter()
+            .max_by(|a, b| {
+                distance_to_segment(**a, first, far)
+                    .total_cmp(&distance_to_segment(**b, first, far))
+            })
+            .copied()
+            .unwrap_or(first);
+        let mut triangle = vec![first, far, third];
+        triangle.sort_by_key(|point| polygon.iter().position(|other| other == point));
+        return triangle;
+    }
+    out
+}
+
+fn inside_triangle(point: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    let d1 = (b - a).perp_dot(point - a);
+    let d2 = (c - b).perp_dot(point - b);
+    let d3 = (a - c).perp_dot(point - c);
+    d1 >= 0. && d2 >= 0. && d3 >= 0.
+}
+
+/**
+Ear clipping for a simple polygon without holes, returns triangle indices.
+
+Self intersecting outlines can run out of proper ears. The remaining points are
+then clipped at convex corners regardless of what lies inside, so every point
+still ends up in `len - 2` triangles, some of them overlapping.
+*/
+pub fn triangulate(polygon: &[Vec2]) -> Vec<u32> {
+    let mut remaining: Vec<usize> = (0..polygon.len()).collect();
+    if signed_area(polygon) < 0. {
+        remaining.reverse();
+    }
+
+    let mut triangles = Vec::new();
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let ear = (0..count).find(|&i| {
+            let a = polygon[remaining[(i + count - 1) % count]];
+            let b = polygon[remaining[i]];
+            let c = polygon[remaining[(i + 1) % count]];
+            if (b - a).perp_dot(c - b) <= 0. {
+                return false;
+            }
+            remaining.iter().all(|&other| {
+                let point = polygon[other];
+                point == a || point == b || point == c || !inside_triangle(point, a, b, c)
+            })
+        });
+
+        let convex = |i: usize| {
+            let a = polygon[remaining[(i + count - 1) % count]];
+            let b = polygon[remaining[i]];
+            let c = polygon[remaining[(i + 1) % count]];
+            (b - a).perp_dot(c - b)
+        };
+        let i = ear.unwrap_or_else(|| {
+            (0..count)
+                .max_by(|a, b| convex(*a).total_cmp(&convex(*b)))
+                .unwrap_or(0)
+        });
+        triangles.extend([
+            remaining[(i + count - 1) % count] as u32,
+            remaining[i] as u32,
+            remaining[(i + 1) % count] as u32,
+        ]);
+        remaining.remove(i);
+    }
+
+    if remaining.len() == 3 {
+        triangles.extend(remaining.iter().map(|&i| i as u32));
+    }
+    triangles
+}
+
+/// Flat 2d mesh made of several polygons
+pub fn polygons_mesh(polygons: &[Vec<Vec2>]) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    for polygon in polygons {
+        let base = positions.len() as u32;
+        positions.extend(polygon.iter().map(|point| [point.x, point.y, 0.]));
+        indices.extend(triangulate(polygon).iter().map(|index| base + index));
+    }
+
+    let normals = vec![[0., 0., 1.]; positions.len()];
+    let uvs = vec![[0., 0.]; positions.len()];
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> (Vec<bool>, usize, usize) {
+        let cells = rows
+            .iter()
+            .flat_map(|row| row.chars().map(|cell| cell == '#'))
+            .collect();
+        (cells, rows[0].len(), rows.len())
+    }
+
+    fn triangles_area(polygon: &[Vec2], indices: &[u32]) -> f32 {
+        indices
+            .chunks(3)
+            .map(|t| {
+                signed_area(&[
+                    polygon[t[0] as usize],
+                    polygon[t[1] as usize],
+                    polygon[t[2] as usize],
+                ])
+                .abs()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn square_traces_one_loop() {
+        let (cells, width, height) = grid(&["##", "##"]);
+        let loops = marching_squares(&cells, width, height);
+        assert_eq!(loops.len(), 1);
+        // The 3x3 square around the samples with its four corners cut off
+        assert_eq!(signed_area(&loops[0]).abs(), 3.5);
+        assert!(contains_point(&loops[0], Vec2::new(0.5, 0.5)));
+        assert!(!contains_point(&loops[0], Vec2::new(2., 0.5)));
+    }
+
+    #[test]
+    fn l_shape_leaves_the_corner_out() {
+        let (cells, width, height) = grid(&["#.", "##"]);
+        let loops = marching_squares(&cells, width, height);
+        assert_eq!(loops.len(), 1);
+        for inside in [Vec2::new(0., 0.), Vec2::new(0., 1.), Vec2::new(1., 1.)] {
+            assert!(contains_point(&loops[0], inside), "{}", inside);
+        }
+        assert!(!contains_point(&loops[0], Vec2::new(1., 0.)));
+    }
+
+    #[test]
+    fn saddles_join_diagonal_cells() {
+        // Case 10 then case 5, both link the filled corners through the middle
+        for rows in [["#.", ".#"], [".#", "#."]] {
+            let (cells, width, height) = grid(&rows);
+            let loops = marching_squares(&cells, width, height);
+            assert_eq!(loops.len(), 1, "{:?}", rows);
+            for y in 0..2 {
+                for x in 0..2 {
+                    let point = Vec2::new(x as f32, y as f32);
+                    assert_eq!(
+                        contains_point(&loops[0], point),
+                        cells[y * width + x],
+                        "{:?} at {}",
+                        rows,
+                        point
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn simplify_drops_collinear_points() {
+        let square: Vec<Vec2> = [
+            (0., 0.),
+            (1., 0.),
+            (2., 0.),
+            (2., 1.),
+            (2., 2.),
+            (1., 2.),
+            (0., 2.),
+            (0., 1.),
+        ]
+        .into_iter()
+        .map(Vec2::from)
+        .collect();
+        let simplified = simplify(&square, 0.1);
+        assert_eq!(simplified.len(), 4);
+        assert_eq!(signed_area(&simplified).abs(), 4.);
+    }
+
+    #[test]
+    fn simplify_keeps_a_triangle() {
+        let (cells, width, height) = grid(&["###", "###", "###"]);
+        let outline = &marching_squares(&cells, width, height)[0];
+        let simplified = simplify(outline, 100.);
+        assert_eq!(simplified.len(), 3);
+        assert!(signed_area(&simplified).abs() > 0.);
+    }
+
+    #[test]
+    fn triangulate_covers_simple_polygons() {
+        let (cells, width, height) = grid(&["#.", "##"]);
+        let outline = &marching_squares(&cells, width, height)[0];
+        let indices = triangulate(outline);
+        assert_eq!(indices.len(), (outline.len() - 2) * 3);
+        let area = triangles_area(outline, &indices);
+        assert!((area - signed_area(outline).abs()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn triangulate_uses_every_point_of_a_bowtie() {
+        let bowtie: Vec<Vec2> = [(0., 0.), (2., 2.), (2., 0.), (0., 2.)]
+            .into_iter()
+            .map(Vec2::from)
+            .collect();
+        let indices = triangulate(&bowtie);
+        assert_eq!(indices.len(), 6);
+        for point in 0..bowtie.len() as u32 {
+            assert!(indices.contains(&point));
+        }
+    }
+}
@@ -1,9 +1,32 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+mod achievements;
+mod big_number;
+mod dialogue;
+mod drops;
+mod economy;
+mod game_events;
+mod geometry;
 mod main_menu;
 mod map;
+mod map_camera;
+mod map_extract;
+mod map_legend;
+mod map_markers;
+mod materials;
 mod mine_plugin;
+mod mines;
+mod prestige;
+mod quest;
+mod region_unlock;
+mod ron_asset;
+mod routes;
+mod save;
 mod scene_change_plugin;
 mod states;
+mod statistics;
+mod tavern;
+mod timed_events;
+mod travel;
 mod util;
 
 use avian3d::prelude::*;
@@ -16,9 +39,22 @@ use bevy_egui::EguiPlugin;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use scene_change_plugin::SceneChangePlugin;
 
+use crate::achievements::AchievementsPlugin;
+use crate::dialogue::DialoguePlugin;
+use crate::economy::EconomyPlugin;
 use crate::map::MapPlugin;
+use crate::map_camera::MapCameraPlugin;
+use crate::map_markers::MapMarkersPlugin;
 use crate::mine_plugin::MinePlugin;
+use crate::prestige::PrestigePlugin;
+use crate::quest::QuestPlugin;
+use crate::region_unlock::RegionUnlockPlugin;
+use crate::save::SavePlugin;
 use crate::states::{AppState, GameState};
+use crate::statistics::StatisticsPlugin;
+use crate::tavern::TavernPlugin;
+use crate::timed_events::TimedEventsPlugin;
+use crate::travel::TravelPlugin;
 
 fn main() {
     App::new()
@@ -42,8 +78,23 @@ fn main() {
         })
         .add_plugins(WorldInspectorPlugin::new())
         .insert_resource(ClearColor(Color::BLACK))
+        .add_plugins((
+            EconomyPlugin,
+            QuestPlugin,
+            DialoguePlugin,
+            StatisticsPlugin,
+            AchievementsPlugin,
+            PrestigePlugin,
+            RegionUnlockPlugin,
+            TimedEventsPlugin,
+            SavePlugin,
+        ))
         .add_plugins(MinePlugin)
         .add_plugins(MapPlugin)
+        .add_plugins(MapCameraPlugin)
+        .add_plugins(MapMarkersPlugin)
+        .add_plugins(TavernPlugin)
+        .add_plugins(TravelPlugin)
         .add_plugins(SceneChangePlugin)
         //.configure_sets(Update, GameLogic.run_if(in_state(GameState::Mine)))
         .add_systems(
@@ -1,24 +1,106 @@
-use crate::states::GameState;
+use crate::economy::Wallet;
+use crate::game_events::RegionVisited;
+use crate::geometry::polygons_mesh;
+use crate::map_camera::{FocusRegion, VIEW_SIZE, fitted_pan_cam};
+use crate::map_extract::{MapExtract, MapRegion, MaskSamples, extract, mask_pixels, sample};
+use crate::map_legend::{Destination, MaskLegend, RegionDef};
+use crate::quest::QuestLog;
+use crate::region_unlock::{OpenUnlockPrompt, RegionUnlocks};
+use crate::ron_asset::RonAssetPlugin;
+use crate::scene_change_plugin::SceneReady;
+use crate::states::{GameState, SceneTransitionState};
+use crate::travel::OpenTravelPrompt;
 use crate::util::despawn_screen;
+use bevy::asset::RenderAssetUsages;
+use bevy::ecs::system::SystemParam;
+use bevy::image::ImageSampler;
 use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
 use bevy::render::view::RenderLayers;
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on, poll_once};
 use bevy_asset_loader::prelude::*;
-use bevy_pancam::PanCam;
 use bevy_simple_screen_boxing::CameraBox;
-use std::cmp;
-use std::collections::HashMap;
+use std::sync::Arc;
 
 pub struct MapPlugin;
 
 #[derive(Component)]
 pub struct MapSceneTag;
 
+/// What an extraction depends on, the mask content is tracked through its asset events
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MaskKey {
+    mask: AssetId<Image>,
+    waypoint: Option<[u8; 3]>,
+    road: Option<[u8; 3]>,
+}
+
+/// Extraction running on the async compute pool
+#[derive(Component)]
+struct MaskTask {
+    key: MaskKey,
+    task: Task<(MapExtract, MaskSamples)>,
+}
+
+/// Last extraction, reused until the mask is modified or the legend colours change
+#[derive(Resource)]
+struct MaskCache {
+    key: MaskKey,
+    extract: Arc<MapExtract>,
+    /// Mask sampled every [`FOG_SCALE`] pixels, the fog is painted from it
+    samples: Arc<MaskSamples>,
+}
+
+/// The mask has been processed and [`MaskCache`] is up to date
+#[derive(Event)]
+struct MaskReady;
+
+/// Fog texels per mask pixel, the overlay is upscaled with linear filtering
+const FOG_SCALE: u32 = 4;
+
+/// Darkens locked regions, generated from the mask
+#[derive(Component)]
+struct Fog;
+
+/// Legend id of a clickable region
+#[derive(Component, Debug, Clone)]
+pub struct RegionId(pub String);
+
+/// Outline of a region as traced from the mask, in world coordinates
+#[derive(Component, Debug, Clone)]
+struct RegionOutline(Vec<Vec<Vec2>>);
+
+/// Map space box around a region, what the camera zooms onto
+#[derive(Component, Debug, Clone)]
+pub struct RegionBounds(pub Rect);
+
+#[derive(Component)]
+struct Hovered;
+
+#[derive(Resource)]
+struct RegionMaterials {
+    normal: Handle<ColorMaterial>,
+    hovered: Handle<ColorMaterial>,
+}
+
+/// Follows the cursor while a region is hovered
+#[derive(Component)]
+struct RegionTooltip;
+
+/// Legend entries of the clickable regions found in the mask
+#[derive(Resource, Default, Debug)]
+pub struct MapRegions {
+    pub defs: Vec<RegionDef>,
+}
+
 #[derive(AssetCollection, Resource)]
 struct SceneAssets {
     #[asset(path = "private/map.png")]
     map: Handle<Image>,
     #[asset(path = "private/mask.png")]
     mask: Handle<Image>,
+    #[asset(path = "data/map.legend.ron")]
+    legend: Handle<MaskLegend>,
 }
 
 #[derive(States, Default, Clone, Eq, PartialEq, Hash, Debug)]
@@ -31,7 +113,8 @@ enum MyLoadingStates {
 
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<MyLoadingStates>()
+        app.add_plugins(RonAssetPlugin::<MaskLegend>::new(&["legend.ron"]))
+            .init_state::<MyLoadingStates>()
             .add_loading_state(
                 LoadingState::new(MyLoadingStates::Started)
                     .continue_to_state(MyLoadingStates::Ready)
@@ -44,90 +127,262 @@ impl Plugin for MapPlugin {
                 },
             )
             .add_systems(OnEnter(MyLoadingStates::Ready), setup)
+            .add_observer(spawn_regions)
+            .add_systems(Update, invalidate_mask_cache)
             .add_systems(OnExit(GameState::Map), despawn_screen::<MapSceneTag>)
-            .add_systems(Update, (update,).run_if(in_state(GameState::Map)));
+            .add_systems(
+                Update,
+                (
+                    update,
+                    poll_mask_task,
+                    update_fog.run_if(in_state(MyLoadingStates::Ready)),
+                    draw_hovered_outline,
+                    move_tooltip,
+                )
+                    .run_if(in_state(GameState::Map)),
+            );
     }
 }
 
 fn setup(
     mut commands: Commands,
     assets: Res<SceneAssets>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut images: ResMut<Assets<Image>>,
+    images: Res<Assets<Image>>,
+    legends: Res<Assets<MaskLegend>>,
+    cache: Option<Res<MaskCache>>,
 ) {
+    let map_size = images
+        .get(&assets.map)
+        .map(|map| map.size_f32())
+        .unwrap_or(VIEW_SIZE);
+    let pan_cam = fitted_pan_cam(map_size);
+    let scale = pan_cam.max_scale;
+
     commands.spawn((
         Camera2d,
         Camera {
             order: 0,
             ..default()
         },
-        PanCam::default(),
+        pan_cam,
         CameraBox::ResolutionIntegerScale {
-            resolution: Vec2::new(1920., 1080.),
+            resolution: VIEW_SIZE,
             allow_imperfect_aspect_ratios: true,
         },
         RenderLayers::layer(0),
         Projection::Orthographic(OrthographicProjection {
             //viewport_origin: Vec2::ZERO,
             scaling_mode: bevy::render::camera::ScalingMode::Fixed {
-                width: 1920.,
-                height: 1080.,
+                width: VIEW_SIZE.x,
+                height: VIEW_SIZE.y,
             },
+            // Start zoomed out on the whole map
+            scale,
             ..OrthographicProjection::default_2d()
         }),
         MapSceneTag,
     ));
 
-    commands
-        .spawn((
-            Sprite {
-                image: assets.map.clone(),
-                //image_mode: SpriteImageMode::Scale(ScalingMode::FillStart),
-                //custom_size: Some(Vec2::new(1920., 1080.)),
-                ..default()
-            },
-            Transform::from_xyz(0., 0., 1.),
-            MapSceneTag,
-        ))
-        .observe(|trigger: Trigger<Pointer<Click>>| {
-            println!("Entity {} clicked!", trigger.target());
+    commands.spawn((
+        Sprite {
+            image: assets.map.clone(),
+            //image_mode: SpriteImageMode::Scale(ScalingMode::FillStart),
+            //custom_size: Some(Vec2::new(1920., 1080.)),
+            ..default()
+        },
+        Transform::from_xyz(0., 0., 1.),
+        MapSceneTag,
+    ));
+
+    let Some(legend) = legends.get(&assets.legend) else {
+        warn!("Map legend is missing, regions are not clickable");
+        commands.trigger(SceneReady {
+            scene: GameState::Map,
+        });
+        return;
+    };
+    let waypoint = legend.waypoint_color.map(|(r, g, b)| [r, g, b]);
+    let road = legend.road_color.map(|(r, g, b)| [r, g, b]);
+    let key = MaskKey {
+        mask: assets.mask.id(),
+        waypoint,
+        road,
+    };
+
+    if cache.is_some_and(|cache| cache.key == key) {
+        commands.trigger(MaskReady);
+        return;
+    }
+
+    let Some((pixels, size)) = images.get(&assets.mask).and_then(|mask| {
+        let pixels = mask_pixels(mask)?;
+        Some((pixels, mask.size()))
+    }) else {
+        warn!("Map mask is missing or unreadable, regions are not clickable");
+        commands.trigger(SceneReady {
+            scene: GameState::Map,
+        });
+        return;
+    };
+
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        (
+            extract(&pixels, size.x, size.y, waypoint, road),
+            sample(&pixels, size.x, size.y, FOG_SCALE),
+        )
+    });
+    commands.spawn((MaskTask { key, task }, MapSceneTag));
+}
+
+/// Hot reloading the mask makes the next visit extract it again
+fn invalidate_mask_cache(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<Image>>,
+    cache: Option<Res<MaskCache>>,
+) {
+    let Some(cache) = cache else {
+        events.clear();
+        return;
+    };
+    if events
+        .read()
+        .any(|event| event.is_modified(cache.key.mask) || event.is_removed(cache.key.mask))
+    {
+        commands.remove_resource::<MaskCache>();
+    }
+}
+
+fn poll_mask_task(mut commands: Commands, mut q_tasks: Query<(Entity, &mut MaskTask)>) {
+    for (entity, mut mask_task) in q_tasks.iter_mut() {
+        let Some((extract, samples)) = block_on(poll_once(&mut mask_task.task)) else {
+            continue;
+        };
+        commands.insert_resource(MaskCache {
+            key: mask_task.key,
+            extract: Arc::new(extract),
+            samples: Arc::new(samples),
         });
+        commands.entity(entity).despawn();
+        commands.trigger(MaskReady);
+    }
+}
+
+/// The map legend and what decides whether its regions are unlocked
+#[derive(SystemParam)]
+struct RegionInfo<'w> {
+    assets: Res<'w, SceneAssets>,
+    legends: Res<'w, Assets<MaskLegend>>,
+    unlocks: Res<'w, RegionUnlocks>,
+    wallet: Res<'w, Wallet>,
+    quests: Res<'w, QuestLog>,
+}
+
+impl RegionInfo<'_> {
+    fn legend(&self) -> Option<&MaskLegend> {
+        self.legends.get(&self.assets.legend)
+    }
+
+    fn def(&self, id: &str) -> Option<&RegionDef> {
+        self.legend()?.by_id(id)
+    }
+}
 
-    let (boxes, points) = match images.get_mut(&assets.mask) {
-        Some(image) => process_map(image),
-        None => (HashMap::new(), Vec::new()),
+fn spawn_regions(
+    _trigger: Trigger<MaskReady>,
+    mut commands: Commands,
+    info: RegionInfo,
+    cache: Res<MaskCache>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Some(legend) = info.legend() else {
+        return;
     };
+    let extract = cache.extract.clone();
+    legend.validate(&extract.colors);
 
-    let mat = materials.add(Color::linear_rgba(0.5, 0.5, 0.33, 0.75));
-    let mat2 = materials.add(Color::linear_rgba(0.75, 0.75, 0.75, 0.85));
+    // Colours without a legend entry are only reported, not clickable
+    let mut regions: Vec<(&RegionDef, MapRegion)> = extract
+        .regions
+        .iter()
+        .filter_map(|(color, region)| Some((legend.by_color(*color)?, region.clone())))
+        .collect();
 
-    for point in points {
-        let mesh = meshes.add(Rhombus::new(15., 15.));
+    commands.insert_resource(MapRegions {
+        defs: regions.iter().map(|(def, _)| (*def).clone()).collect(),
+    });
+
+    let mut graph = extract.graph.clone();
+    for (def, region) in regions.iter() {
+        graph.add_region(&def.id, region.anchor());
+    }
+    commands.insert_resource(graph);
+
+    if let Some(mask) = images.get(&info.assets.mask) {
+        let size = mask.size().as_vec2();
+        let fog = images.add(fog_image(&cache.samples, legend, &info.unlocks));
         commands.spawn((
-            Mesh2d(mesh),
-            MeshMaterial2d(mat2.clone()),
-            Transform::from_xyz(point.x, point.y, 21.),
+            Sprite {
+                image: fog,
+                custom_size: Some(size),
+                ..default()
+            },
+            Transform::from_xyz(0., 0., 30.),
+            Pickable::IGNORE,
+            Name::new("Fog"),
+            Fog,
             MapSceneTag,
         ));
     }
 
-    for (color, rect) in boxes {
-        println!("{:?}: {:?}", color, rect);
-        let size = rect.max - rect.min;
-        let mesh = meshes.add(Rectangle::from_size(size));
-        let mid = rect.min.midpoint(rect.max);
+    let region_materials = RegionMaterials {
+        normal: materials.add(Color::linear_rgba(0.5, 0.5, 0.33, 0.75)),
+        hovered: materials.add(Color::linear_rgba(0.8, 0.8, 0.5, 0.85)),
+    };
+    // Regions painted inside bigger ones have to stay on top to be clickable
+    regions.sort_by(|a, b| b.1.area().total_cmp(&a.1.area()));
+
+    for (i, (def, region)) in regions.into_iter().enumerate() {
+        let mesh = meshes.add(polygons_mesh(&region.outlines));
         commands
             .spawn((
                 Mesh2d(mesh),
-                MeshMaterial2d(mat.clone()),
-                Transform::from_xyz(mid.x, mid.y, 20.),
+                MeshMaterial2d(region_materials.normal.clone()),
+                Transform::from_xyz(0., 0., 20. + i as f32 * 0.01),
+                Name::new(def.name.clone()),
+                RegionId(def.id.clone()),
+                RegionOutline(region.outlines),
+                RegionBounds(region.bounds),
                 MapSceneTag,
             ))
-            .observe(|trigger: Trigger<Pointer<Click>>| {
-                println!("Entity {} clicked!", trigger.target());
-            });
+            .observe(region_click)
+            .observe(region_over)
+            .observe(region_out);
     }
+    commands.insert_resource(region_materials);
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Px(8.)),
+            ..default()
+        },
+        BackgroundColor(Color::linear_rgba(0.02, 0.03, 0.05, 0.85)),
+        GlobalZIndex(5),
+        Visibility::Hidden,
+        Pickable::IGNORE,
+        Name::new("Region tooltip"),
+        RegionTooltip,
+        MapSceneTag,
+        children![(Text::default(), Pickable::IGNORE)],
+    ));
+
+    // Regions are only known once the mask has been processed
+    commands.trigger(SceneReady {
+        scene: GameState::Map,
+    });
 
     //commands.spawn((
     //    Sprite {
@@ -141,79 +396,177 @@ fn setup(
     //));
 }
 
-fn process_map(image: &Image) -> (HashMap<String, Rect>, Vec<Vec2>) {
-    let mut boxes: HashMap<String, URect> = HashMap::new();
-    let mut points: Vec<Vec2> = Vec::new();
+fn region_click(
+    trigger: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    info: RegionInfo,
+    transition: Res<State<SceneTransitionState>>,
+    q_regions: Query<(&RegionId, &RegionBounds)>,
+) {
+    // The map is about to be left
+    if *transition.get() != SceneTransitionState::Normal {
+        return;
+    }
+    let Ok((region, bounds)) = q_regions.get(trigger.target()) else {
+        return;
+    };
+    let Some(def) = info.def(&region.0) else {
+        return;
+    };
+    commands.trigger(FocusRegion { bounds: bounds.0 });
 
-    let width = image.size().x as u32;
-    let height = image.size().y as u32;
+    if !info.unlocks.is_unlocked(def) {
+        commands.trigger(OpenUnlockPrompt {
+            region: def.clone(),
+        });
+    } else if def.destination.is_some() {
+        commands.trigger(OpenTravelPrompt {
+            region: def.clone(),
+        });
+    } else {
+        commands.trigger(RegionVisited {
+            region: region.0.clone(),
+        });
+    }
+}
 
-    let half_width = width / 2;
-    let half_height = height / 2;
+fn region_over(
+    trigger: Trigger<Pointer<Over>>,
+    mut commands: Commands,
+    info: RegionInfo,
+    region_materials: Res<RegionMaterials>,
+    mut q_regions: Query<(&RegionId, &mut MeshMaterial2d<ColorMaterial>)>,
+    mut q_tooltip: Query<(&mut Visibility, &Children), With<RegionTooltip>>,
+    mut q_text: Query<&mut Text>,
+) {
+    let Ok((region, mut material)) = q_regions.get_mut(trigger.target()) else {
+        return;
+    };
+    commands.entity(trigger.target()).insert(Hovered);
+    material.0 = region_materials.hovered.clone();
 
-    // Instead of checkin all points, use grid of at least 100 lines
-    let step = cmp::min(width, height) / 100;
-    let x_steps = width / step;
-    let y_steps = height / step;
+    let Some(def) = info.def(&region.0) else {
+        return;
+    };
+
+    let mut lines = vec![def.name.clone()];
+    if info.unlocks.is_unlocked(def) {
+        match &def.destination {
+            Some(Destination::Mine(_)) => lines.push("Mine, click to travel".to_string()),
+            Some(Destination::Tavern) => lines.push("Tavern, click to travel".to_string()),
+            None => {}
+        }
+    } else {
+        lines.push("Locked".to_string());
+        for (text, met) in def.requirements(&info.wallet, &info.quests) {
+            lines.push(format!("{} {}", if met { "[x]" } else { "[ ]" }, text));
+        }
+    }
 
-    let flip_y = Vec2 { x: 1., y: -1. };
+    let Ok((mut visibility, children)) = q_tooltip.single_mut() else {
+        return;
+    };
+    *visibility = Visibility::Inherited;
+    for child in children.iter() {
+        if let Ok(mut text) = q_text.get_mut(child) {
+            text.0 = lines.join("\n");
+        }
+    }
+}
 
-    let offset = Vec2 {
-        x: half_width as f32,
-        y: half_height as f32,
+fn region_out(
+    trigger: Trigger<Pointer<Out>>,
+    mut commands: Commands,
+    region_materials: Res<RegionMaterials>,
+    mut q_regions: Query<&mut MeshMaterial2d<ColorMaterial>, With<RegionId>>,
+    mut q_tooltip: Query<&mut Visibility, With<RegionTooltip>>,
+) {
+    let Ok(mut material) = q_regions.get_mut(trigger.target()) else {
+        return;
     };
+    commands.entity(trigger.target()).remove::<Hovered>();
+    material.0 = region_materials.normal.clone();
+
+    if let Ok(mut visibility) = q_tooltip.single_mut() {
+        *visibility = Visibility::Hidden;
+    }
+}
 
-    for x_step in 0..x_steps {
-        for y_step in 0..y_steps {
-            // Restore steps to pixels
-            let x = x_step * step;
-            let y = y_step * step;
-            if let Ok(clr) = image.get_color_at(x, y) {
-                if clr.alpha() == 1. {
-                    let name = clr.hue().to_string();
-                    let pos = UVec2 { x, y };
-
-                    points.push((pos.as_vec2() - offset) * flip_y);
-
-                    let rect = boxes.entry(name).or_insert(URect { min: pos, max: pos });
-
-                    // Skip for fresh entries
-                    if pos == rect.min && rect.min == rect.max {
-                        continue;
-                    }
-
-                    if pos.x < rect.min.x {
-                        rect.min.x = pos.x;
-                    } else if pos.x > rect.max.x {
-                        rect.max.x = pos.x;
-                    }
-
-                    if pos.y < rect.min.y {
-                        rect.min.y = pos.y;
-                    } else if pos.y > rect.max.y {
-                        rect.max.y = pos.y;
-                    }
-                }
-            }
+fn draw_hovered_outline(mut gizmos: Gizmos, q_hovered: Query<&RegionOutline, With<Hovered>>) {
+    for outline in q_hovered.iter() {
+        for polygon in outline.0.iter() {
+            gizmos.linestrip_2d(
+                polygon.iter().chain(polygon.first()).copied(),
+                Color::linear_rgb(1., 0.9, 0.4),
+            );
         }
     }
+}
 
-    return (
-        boxes
-            .iter()
-            .map(|(k, v)| {
-                (
-                    k.clone(),
-                    // Bevy sprite coordinates are relative to center by default
-                    Rect {
-                        min: (v.min.as_vec2() - offset) * flip_y,
-                        max: (v.max.as_vec2() - offset) * flip_y,
-                    },
-                )
-            })
-            .collect(),
-        points,
+fn move_tooltip(q_window: Query<&Window>, mut q_tooltip: Query<&mut Node, With<RegionTooltip>>) {
+    let (Ok(window), Ok(mut node)) = (q_window.single(), q_tooltip.single_mut()) else {
+        return;
+    };
+    if let Some(cursor) = window.cursor_position() {
+        node.left = Val::Px(cursor.x + 16.);
+        node.top = Val::Px(cursor.y + 16.);
+    }
+}
+
+fn fog_image(samples: &MaskSamples, legend: &MaskLegend, unlocks: &RegionUnlocks) -> Image {
+    let locked: Vec<[u8; 3]> = legend
+        .regions
+        .iter()
+        .filter(|region| !unlocks.is_unlocked(region))
+        .map(|region| [region.color.0, region.color.1, region.color.2])
+        .collect();
+
+    let dark = Color::srgba(0.02, 0.02, 0.05, 0.85)
+        .to_srgba()
+        .to_u8_array();
+    let data = samples
+        .colors
+        .iter()
+        .flat_map(|color| match color {
+            Some(color) if locked.contains(color) => dark,
+            _ => [0; 4],
+        })
+        .collect();
+
+    let mut fog = Image::new(
+        Extent3d {
+            width: samples.width,
+            height: samples.height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
     );
+    fog.sampler = ImageSampler::linear();
+    fog
+}
+
+/// Lifts the fog as soon as a region gets unlocked
+fn update_fog(
+    unlocks: Res<RegionUnlocks>,
+    assets: Res<SceneAssets>,
+    legends: Res<Assets<MaskLegend>>,
+    cache: Option<Res<MaskCache>>,
+    mut images: ResMut<Assets<Image>>,
+    q_fog: Query<&Sprite, With<Fog>>,
+) {
+    if !unlocks.is_changed() {
+        return;
+    }
+    let (Ok(sprite), Some(legend), Some(cache)) =
+        (q_fog.single(), legends.get(&assets.legend), cache)
+    else {
+        return;
+    };
+
+    images.insert(&sprite.image, fog_image(&cache.samples, legend, &unlocks));
 }
 
 fn update() {}
@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+use bevy_pancam::PanCam;
+
+use crate::states::GameState;
+
+/// Virtual resolution of the `CameraBox`, what the camera shows at scale 1
+pub const VIEW_SIZE: Vec2 = Vec2::new(1920., 1080.);
+
+/// Closest zoom, in screen pixels per map pixel
+const MAX_MAGNIFICATION: f32 = 4.;
+
+/// Fraction of the way to the target covered each 1/60 s
+const FOCUS_SMOOTHING: f32 = 0.12;
+
+/// Room left around a focused region, relative to its size
+const FOCUS_MARGIN: f32 = 1.6;
+
+pub struct MapCameraPlugin;
+
+/// Zooms the map camera onto an area
+#[derive(Event, Debug, Clone)]
+pub struct FocusRegion {
+    pub bounds: Rect,
+}
+
+/// Where the camera is gliding to, panning by hand is paused meanwhile
+#[derive(Component, Debug)]
+struct CameraFocus {
+    position: Vec2,
+    scale: f32,
+}
+
+impl Plugin for MapCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(focus_region)
+            .add_systems(Update, glide_to_focus.run_if(in_state(GameState::Map)));
+    }
+}
+
+/**
+`PanCam` that can't leave a `map_size` sprite centred on the origin.
+
+The farthest zoom shows the whole map, the closest one is limited to
+[`MAX_MAGNIFICATION`] screen pixels per map pixel.
+*/
+pub fn fitted_pan_cam(map_size: Vec2) -> PanCam {
+    let fit = (map_size / VIEW_SIZE).min_element();
+    PanCam {
+        min_scale: (1. / MAX_MAGNIFICATION).min(fit),
+        max_scale: fit,
+        min_x: -map_size.x / 2.,
+        max_x: map_size.x / 2.,
+        min_y: -map_size.y / 2.,
+        max_y: map_size.y / 2.,
+        ..default()
+    }
+}
+
+/// Keeps a `scale` view centred on `position` inside the pan bounds
+fn clamp_to_bounds(pan_cam: &PanCam, position: Vec2, scale: f32) -> Vec2 {
+    let half_view = VIEW_SIZE * scale / 2.;
+    let min = Vec2::new(pan_cam.min_x, pan_cam.min_y) + half_view;
+    let max = Vec2::new(pan_cam.max_x, pan_cam.max_y) - half_view;
+    position.clamp(min.min(max), max.max(min))
+}
+
+fn focus_region(
+    trigger: Trigger<FocusRegion>,
+    mut commands: Commands,
+    mut q_camera: Query<(Entity, &mut PanCam)>,
+) {
+    let Ok((entity, mut pan_cam)) = q_camera.single_mut() else {
+        return;
+    };
+    let bounds = trigger.event().bounds;
+
+    let scale = (bounds.size() * FOCUS_MARGIN / VIEW_SIZE)
+        .max_element()
+        .clamp(pan_cam.min_scale, pan_cam.max_scale);
+    let position = clamp_to_bounds(&pan_cam, bounds.center(), scale);
+
+    pan_cam.enabled = false;
+    commands
+        .entity(entity)
+        .insert(CameraFocus { position, scale });
+}
+
+fn glide_to_focus(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut q_camera: Query<(
+        Entity,
+        &CameraFocus,
+        &mut PanCam,
+        &mut Transform,
+        &mut Projection,
+    )>,
+) {
+    // Frame rate independent exponential smoothing
+    let t = 1. - (1. - FOCUS_SMOOTHING).powf(time.delta_secs() * 60.);
+
+    for (entity, focus, mut pan_cam, mut transform, mut projection) in q_camera.iter_mut() {
+        let Projection::Orthographic(projection) = projection.as_mut() else {
+            continue;
+        };
+
+        projection.scale += (focus.scale - projection.scale) * t;
+        let position = transform.translation.truncate().lerp(focus.position, t);
+        transform.translation = position.extend(transform.translation.z);
+
+        let arrived = position.distance(focus.position) < 0.5
+            && (projection.scale - focus.scale).abs() < 0.001;
+        if arrived {
+            projection.scale = focus.scale;
+            transform.translation = focus.position.extend(transform.translation.z);
+            pan_cam.enabled = true;
+            commands.entity(entity).remove::<CameraFocus>();
+        }
+    }
+}
@@ -0,0 +1,401 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureFormat;
+use std::collections::BTreeMap;
+
+use crate::geometry::{contains_point, marching_squares, signed_area, simplify};
+use crate::routes::RouteGraph;
+
+/// Distance in pixels the simplified outlines may stray from the traced ones
+const SIMPLIFY_EPSILON: f32 = 2.;
+
+/// Area of one mask colour, in map sprite coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapRegion {
+    /// Covers every pixel of the colour, edges included
+    pub bounds: Rect,
+    /// Simplified outer contours, holes are left out
+    pub outlines: Vec<Vec<Vec2>>,
+}
+
+impl MapRegion {
+    pub fn area(&self) -> f32 {
+        self.outlines
+            .iter()
+            .map(|outline| signed_area(outline).abs())
+            .sum()
+    }
+
+    /// Middle of the biggest outline, where routes start and end
+    pub fn anchor(&self) -> Vec2 {
+        self.outlines
+            .iter()
+            .max_by(|a, b| signed_area(a).abs().total_cmp(&signed_area(b).abs()))
+            .map(|outline| outline.iter().sum::<Vec2>() / outline.len() as f32)
+            .unwrap_or_else(|| self.bounds.center())
+    }
+}
+
+/**
+What the map needs from the mask.
+
+Everything is keyed and sorted by colour so the same mask always gives the
+same output, whatever order the pixels were visited in.
+*/
+#[derive(Default, Debug)]
+pub struct MapExtract {
+    /// Every opaque colour of the mask, sorted
+    pub colors: Vec<[u8; 3]>,
+    pub regions: BTreeMap<[u8; 3], MapRegion>,
+    /// Waypoints and roads, regions are attached when spawning
+    pub graph: RouteGraph,
+}
+
+/// Copy of the mask as tightly packed RGBA8 bytes
+pub fn mask_pixels(image: &Image) -> Option<Vec<u8>> {
+    match image.texture_descriptor.format {
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm => image.data.clone(),
+        _ => image.convert(TextureFormat::Rgba8UnormSrgb)?.data,
+    }
+}
+
+/// Mask colours on a coarse grid, enough to paint overlays without reading the image again
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct MaskSamples {
+    pub width: u32,
+    pub height: u32,
+    /// Row-major, `None` where the mask isn't fully opaque
+    pub colors: Vec<Option<[u8; 3]>>,
+}
+
+/// Every `step`-th pixel of raw RGBA8 `pixels` in both directions, also meant for the async pool
+pub fn sample(pixels: &[u8], width: u32, height: u32, step: u32) -> MaskSamples {
+    let step = step.max(1);
+    let (samples_x, samples_y) = ((width / step).max(1), (height / step).max(1));
+    let mut colors = Vec::with_capacity((samples_x * samples_y) as usize);
+    for y in 0..samples_y {
+        for x in 0..samples_x {
+            let i = ((y * step * width + x * step) * 4) as usize;
+            colors.push(match pixels.get(i..i + 4) {
+                Some([r, g, b, 255]) => Some([*r, *g, *b]),
+                _ => None,
+            });
+        }
+    }
+    MaskSamples {
+        width: samples_x,
+        height: samples_y,
+        colors,
+    }
+}
+
+/**
+Full resolution pass over raw RGBA8 `pixels`, meant to run off the main thread.
+
+Only fully opaque pixels belong to a region. Positions are in map sprite
+coordinates: origin at the image centre, y pointing up.
+*/
+pub fn extract(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    waypoint: Option<[u8; 3]>,
+    road: Option<[u8; 3]>,
+) -> MapExtract {
+    let mut boxes: BTreeMap<[u8; 3], URect> = BTreeMap::new();
+    // Pixel indices per colour, a noisy mask can have thousands of colours
+    let mut covered: BTreeMap<[u8; 3], Vec<u32>> = BTreeMap::new();
+
+    let (w, h) = (width as usize, height as usize);
+    for (i, pixel) in pixels.chunks_exact(4).take(w * h).enumerate() {
+        if pixel[3] != 255 {
+            continue;
+        }
+        let color = [pixel[0], pixel[1], pixel[2]];
+        let pos = UVec2::new((i % w) as u32, (i / w) as u32);
+
+        covered.entry(color).or_default().push(i as u32);
+        boxes
+            .entry(color)
+            .and_modify(|rect| {
+                rect.min = rect.min.min(pos);
+                rect.max = rect.max.max(pos);
+            })
+            .or_insert(URect { min: pos, max: pos });
+    }
+
+    let offset = Vec2::new((width / 2) as f32, (height / 2) as f32);
+    let to_world = |point: Vec2| (point - offset) * Vec2::new(1., -1.);
+
+    let mask_of = |color: Option<[u8; 3]>| -> Vec<bool> {
+        let mut mask = vec![false; w * h];
+        for i in color
+            .and_then(|color| covered.get(&color))
+            .into_iter()
+            .flatten()
+        {
+            mask[*i as usize] = true;
+        }
+        mask
+    };
+    let graph = RouteGraph::build(w, h, &mask_of(waypoint), &mask_of(road), 1., to_world);
+
+    // Roads and waypoints are painted in the mask too, but they are not regions
+    let regions = boxes
+        .iter()
+        .filter(|(color, _)| Some(**color) != waypoint && Some(**color) != road)
+        .map(|(color, rect)| {
+            let region = MapRegion {
+                // Pixel centres sit on whole coordinates, their edges half a pixel away
+                bounds: Rect::from_corners(
+                    to_world(rect.min.as_vec2() - 0.5),
+                    to_world(rect.max.as_vec2() + 0.5),
+                ),
+                outlines: region_outlines(&covered[color], *rect, w, to_world),
+            };
+            (*color, region)
+        })
+        .collect();
+
+    MapExtract {
+        colors: boxes.keys().copied().collect(),
+        regions,
+        graph,
+    }
+}
+
+/// Traces the region on a mask covering only its bounds
+fn region_outlines(
+    covered: &[u32],
+    rect: URect,
+    width: usize,
+    to_world: impl Fn(Vec2) -> Vec2,
+) -> Vec<Vec<Vec2>> {
+    let size = rect.size() + 1;
+    let (local_w, local_h) = (size.x as usize, size.y as usize);
+    let mut mask = vec![false; local_w * local_h];
+    for i in covered {
+        let (x, y) = (*i as usize % width, *i as usize / width);
+        mask[(y - rect.min.y as usize) * local_w + x - rect.min.x as usize] = true;
+    }
+    let origin = rect.min.as_vec2();
+    outlines(&mask, local_w, local_h, |point| to_world(point + origin))
+}
+
+fn outlines(
+    mask: &[bool],
+    width: usize,
+    height: usize,
+    to_world: impl Fn(Vec2) -> Vec2,
+) -> Vec<Vec<Vec2>> {
+    let loops: Vec<Vec<Vec2>> = marching_squares(mask, width, height)
+        .iter()
+        .map(|contour| {
+            let contour: Vec<Vec2> = contour.iter().map(|point| to_world(*point)).collect();
+            simplify(&contour, SIMPLIFY_EPSILON)
+        })
+        .filter(|outline| outline.len() >= 3)
+        .collect();
+
+    // Holes are enclosed by an odd number of other loops
+    loops
+        .iter()
+        .enumerate()
+        .filter(|(i, outline)| {
+            loops
+                .iter()
+                .enumerate()
+                .filter(|(j, other)| i != j && contains_point(other, outline[0]))
+                .count()
+                % 2
+                == 0
+        })
+        .map(|(_, outline)| outline.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::asset::RenderAssetUsages;
+    use bevy::render::render_resource::{Extent3d, TextureDimension};
+
+    const RED: [u8; 3] = [255, 0, 0];
+    const GREEN: [u8; 3] = [0, 255, 0];
+    const WHITE: [u8; 3] = [255, 255, 255];
+    const GREY: [u8; 3] = [128, 128, 128];
+
+    /// [`extract`] for an [`Image`] in any format [`Image::convert`] understands
+    fn extract_image(
+        image: &Image,
+        waypoint: Option<[u8; 3]>,
+        road: Option<[u8; 3]>,
+    ) -> Option<MapExtract> {
+        let pixels = mask_pixels(image)?;
+        Some(extract(
+            &pixels,
+            image.width(),
+            image.height(),
+            waypoint,
+            road,
+        ))
+    }
+
+    fn image(width: u32, height: u32) -> Image {
+        Image::new_fill(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[0, 0, 0, 0],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        )
+    }
+
+    fn paint(image: &mut Image, color: [u8; 3], pixels: impl IntoIterator<Item = (u32, u32)>) {
+        for (x, y) in pixels {
+            image
+                .set_color_at(x, y, Color::srgb_u8(color[0], color[1], color[2]))
+                .unwrap();
+        }
+    }
+
+    fn rect(min: (u32, u32), max: (u32, u32)) -> impl Iterator<Item = (u32, u32)> {
+        (min.1..=max.1).flat_map(move |y| (min.0..=max.0).map(move |x| (x, y)))
+    }
+
+    #[test]
+    fn bounds_cover_the_painted_pixels() {
+        let mut mask = image(10, 8);
+        paint(&mut mask, RED, rect((2, 1), (5, 3)));
+
+        let extract = extract_image(&mask, None, None).unwrap();
+        let region = &extract.regions[&RED];
+
+        // Image centre is (5, 4), y is flipped
+        assert_eq!(region.bounds.min, Vec2::new(-3.5, 0.5));
+        assert_eq!(region.bounds.max, Vec2::new(0.5, 3.5));
+    }
+
+    #[test]
+    fn bounds_grow_in_every_direction() {
+        // Row-major visiting order meets the extremes out of order: right first, left later
+        let mut mask = image(10, 10);
+        paint(&mut mask, RED, [(6, 1), (2, 4), (8, 5), (5, 8), (4, 2)]);
+
+        let extract = extract_image(&mask, None, None).unwrap();
+        let bounds = extract.regions[&RED].bounds;
+
+        assert_eq!(bounds.min, Vec2::new(-3.5, -3.5));
+        assert_eq!(bounds.max, Vec2::new(3.5, 4.5));
+    }
+
+    #[test]
+    fn single_pixel_region_is_kept() {
+        let mut mask = image(6, 6);
+        paint(&mut mask, GREEN, [(3, 3)]);
+
+        let extract = extract_image(&mask, None, None).unwrap();
+        let region = &extract.regions[&GREEN];
+
+        assert_eq!(region.bounds.size(), Vec2::ONE);
+        assert_eq!(region.outlines.len(), 1);
+    }
+
+    #[test]
+    fn transparent_and_translucent_pixels_are_ignored() {
+        let mut mask = image(4, 4);
+        mask.set_color_at(1, 1, Color::srgba_u8(255, 0, 0, 128))
+            .unwrap();
+
+        let extract = extract_image(&mask, None, None).unwrap();
+
+        assert!(extract.colors.is_empty());
+        assert!(extract.regions.is_empty());
+    }
+
+    #[test]
+    fn output_is_sorted_by_colour() {
+        let mut mask = image(8, 8);
+        paint(&mut mask, WHITE, rect((0, 0), (1, 1)));
+        paint(&mut mask, RED, rect((2, 2), (3, 3)));
+        paint(&mut mask, GREEN, rect((4, 4), (5, 5)));
+
+        let extract = extract_image(&mask, None, None).unwrap();
+
+        assert_eq!(extract.colors, vec![GREEN, RED, WHITE]);
+        assert_eq!(
+            extract.regions.keys().copied().collect::<Vec<_>>(),
+            vec![GREEN, RED, WHITE]
+        );
+    }
+
+    #[test]
+    fn same_mask_gives_same_output() {
+        let mut mask = image(16, 16);
+        paint(&mut mask, RED, rect((1, 1), (6, 9)));
+        paint(&mut mask, GREEN, rect((8, 2), (14, 14)));
+
+        let first = extract_image(&mask, None, None).unwrap();
+        let second = extract_image(&mask, None, None).unwrap();
+
+        assert_eq!(first.regions, second.regions);
+    }
+
+    #[test]
+    fn outline_encloses_the_region() {
+        let mut mask = image(12, 12);
+        paint(&mut mask, RED, rect((2, 2), (9, 9)));
+
+        let extract = extract_image(&mask, None, None).unwrap();
+        let region = &extract.regions[&RED];
+
+        assert_eq!(region.outlines.len(), 1);
+        // Marching squares cuts the corners, so a bit less than the 8x8 pixels
+        assert!(region.area() > 56. && region.area() <= 64.);
+        assert!(contains_point(&region.outlines[0], region.anchor()));
+    }
+
+    #[test]
+    fn holes_are_left_out() {
+        let mut mask = image(12, 12);
+        paint(
+            &mut mask,
+            RED,
+            rect((1, 1), (10, 10)).filter(|(x, y)| !(4..=7).contains(x) || !(4..=7).contains(y)),
+        );
+
+        let extract = extract_image(&mask, None, None).unwrap();
+
+        assert_eq!(extract.regions[&RED].outlines.len(), 1);
+    }
+
+    #[test]
+    fn samples_take_every_step_pixel() {
+        let mut mask = image(8, 4);
+        paint(&mut mask, RED, rect((0, 0), (3, 3)));
+        paint(&mut mask, GREEN, [(4, 0)]);
+        let pixels = mask_pixels(&mask).unwrap();
+        let samples = sample(&pixels, 8, 4, 4);
+        assert_eq!((samples.width, samples.height), (2, 1));
+        assert_eq!(samples.colors, vec![Some(RED), Some(GREEN)]);
+    }
+
+    #[test]
+    fn route_colours_are_not_regions() {
+        let mut mask = image(12, 4);
+        paint(&mut mask, WHITE, [(1, 1), (10, 1)]);
+        paint(&mut mask, GREY, rect((2, 1), (9, 1)));
+        paint(&mut mask, RED, rect((0, 3), (3, 3)));
+
+        let extract = extract_image(&mask, Some(WHITE), Some(GREY)).unwrap();
+
+        assert_eq!(extract.colors, vec![GREY, RED, WHITE]);
+        assert_eq!(
+            extract.regions.keys().copied().collect::<Vec<_>>(),
+            vec![RED]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::big_number::BigNum;
+use crate::states::GameState;
+
+/// Where clicking a region takes the player
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub enum Destination {
+    /// Mine id from `data/mines.mines.ron`
+    Mine(String),
+    Tavern,
+}
+
+impl Destination {
+    pub fn state(&self) -> GameState {
+        match self {
+            Destination::Mine(_) => GameState::Mine,
+            Destination::Tavern => GameState::Tavern,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RegionDef {
+    /// Exact mask colour, alpha is ignored
+    pub color: (u8, u8, u8),
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub unlock_cost: Option<BigNum>,
+    /// Contract that has to be completed before the region can be unlocked
+    #[serde(default)]
+    pub unlock_quest: Option<String>,
+    #[serde(default)]
+    pub destination: Option<Destination>,
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+/**
+Describes the regions painted into `private/mask.png`.
+
+The mask only carries colours, the legend gives each of them a stable id and
+everything the game needs to know about the region.
+*/
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct MaskLegend {
+    pub regions: Vec<RegionDef>,
+    /// Blobs of this colour are route waypoints
+    #[serde(default)]
+    pub waypoint_color: Option<(u8, u8, u8)>,
+    /// Roads connecting the waypoints
+    #[serde(default)]
+    pub road_color: Option<(u8, u8, u8)>,
+}
+
+impl MaskLegend {
+    pub fn by_color(&self, color: [u8; 3]) -> Option<&RegionDef> {
+        self.regions
+            .iter()
+            .find(|region| region.color == (color[0], color[1], color[2]))
+    }
+
+    pub fn by_id(&self, id: &str) -> Option<&RegionDef> {
+        self.regions.iter().find(|region| region.id == id)
+    }
+
+    /// Logs every mismatch between the legend and the colours found in the mask
+    pub fn validate(&self, found: &[[u8; 3]]) -> bool {
+        let mut valid = true;
+
+        let routes = [self.waypoint_color, self.road_color];
+        for color in found {
+            let is_route = routes.contains(&Some((color[0], color[1], color[2])));
+            if !is_route && self.by_color(*color).is_none() {
+                warn!(
+                    "Mask colour rgb({}, {}, {}) is missing from the legend",
+                    color[0], color[1], color[2]
+                );
+                valid = false;
+            }
+        }
+
+        for (i, region) in self.regions.iter().enumerate() {
+            let (r, g, b) = region.color;
+            if !found.contains(&[r, g, b]) {
+                warn!(
+                    "Legend region {} rgb({}, {}, {}) is not painted in the mask",
+                    region.id, r, g, b
+                );
+                valid = false;
+            }
+            if self.regions[..i].iter().any(|other| other.id == region.id) {
+                warn!("Legend region id {} is used more than once", region.id);
+                valid = false;
+            }
+            if self.regions[..i]
+                .iter()
+                .any(|other| other.color == region.color)
+            {
+                warn!(
+                    "Legend colour rgb({}, {}, {}) is used more than once",
+                    r, g, b
+                );
+                valid = false;
+            }
+        }
+
+        valid
+    }
+}
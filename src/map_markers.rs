@@ -0,0 +1,396 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_pancam::PanCam;
+use std::collections::BTreeSet;
+
+use crate::game_events::{RegionUnlocked, RegionVisited};
+use crate::map::{MapRegions, MapSceneTag, RegionBounds, RegionId};
+use crate::map_camera::FocusRegion;
+use crate::quest::{OpenQuestLog, QuestBook, QuestLog, Quests};
+use crate::routes::RouteGraph;
+use crate::states::GameState;
+use crate::timed_events::TimedEvents;
+use crate::travel::{OpenTravelPrompt, PlayerLocation};
+
+/// Marker size and spacing, in screen pixels
+const MARKER_RADIUS: f32 = 16.;
+const MARKER_SPACING: f32 = 38.;
+const MARKER_LIFT: f32 = 24.;
+
+/// Regions whose anchors are closer than this on screen share one marker
+const CLUSTER_DISTANCE: f32 = 90.;
+
+pub struct MapMarkersPlugin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MarkerKind {
+    Location,
+    Quest,
+    Event,
+    Unlocked,
+}
+
+impl MarkerKind {
+    fn color(&self) -> Color {
+        match self {
+            MarkerKind::Location => Color::linear_rgb(0.3, 0.6, 1.),
+            MarkerKind::Quest => Color::linear_rgb(1., 0.85, 0.2),
+            MarkerKind::Event => Color::linear_rgb(1., 0.35, 0.2),
+            MarkerKind::Unlocked => Color::linear_rgb(0.3, 0.9, 0.4),
+        }
+    }
+
+    fn glyph(&self) -> &'static str {
+        match self {
+            MarkerKind::Location => "@",
+            MarkerKind::Quest => "!",
+            MarkerKind::Event => "*",
+            MarkerKind::Unlocked => "+",
+        }
+    }
+}
+
+/// Regions unlocked since the player last went there, not saved
+#[derive(Resource, Default, Debug)]
+pub struct NewUnlocks(pub BTreeSet<String>);
+
+/// Icon pinned to a region anchor, `offset` is in screen pixels
+#[derive(Component, Debug)]
+struct MapMarker {
+    kind: MarkerKind,
+    region: String,
+    anchor: Vec2,
+    offset: Vec2,
+}
+
+/// Stands in for markers of nearby regions when zoomed out
+#[derive(Component, Debug)]
+struct MarkerCluster {
+    regions: Vec<String>,
+    centre: Vec2,
+}
+
+impl Plugin for MapMarkersPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NewUnlocks>()
+            .add_observer(
+                |trigger: Trigger<RegionUnlocked>, mut new_unlocks: ResMut<NewUnlocks>| {
+                    new_unlocks.0.insert(trigger.event().region.clone());
+                },
+            )
+            .add_observer(
+                |trigger: Trigger<RegionVisited>, mut new_unlocks: ResMut<NewUnlocks>| {
+                    new_unlocks.0.remove(&trigger.event().region);
+                },
+            )
+            .add_systems(
+                Update,
+                (sync_markers, cluster_markers, place_markers)
+                    .chain()
+                    .run_if(in_state(GameState::Map)),
+            );
+    }
+}
+
+/// Everything the markers are made from
+#[derive(SystemParam)]
+struct MarkerSources<'w> {
+    asset_server: Res<'w, AssetServer>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<ColorMaterial>>,
+    location: Res<'w, PlayerLocation>,
+    log: Res<'w, QuestLog>,
+    quests: Res<'w, Quests>,
+    books: Res<'w, Assets<QuestBook>>,
+    events: Res<'w, TimedEvents>,
+    new_unlocks: Res<'w, NewUnlocks>,
+}
+
+impl MarkerSources<'_> {
+    /// Everything worth a marker right now, grouped by region
+    fn wanted(&self) -> Vec<(MarkerKind, String, String)> {
+        let mut wanted = vec![(
+            MarkerKind::Location,
+            self.location.region.clone(),
+            "You are here".to_string(),
+        )];
+        if let Some(book) = self.books.get(&self.quests.book) {
+            for quest in self.log.active.iter() {
+                let Some(def) = book.get(&quest.id) else {
+                    continue;
+                };
+                if let Some(region) = def.region() {
+                    wanted.push((MarkerKind::Quest, region.to_string(), def.title.clone()));
+                }
+            }
+        }
+        for event in self.events.active.iter() {
+            wanted.push((MarkerKind::Event, event.region.clone(), event.title.clone()));
+        }
+        for region in self.new_unlocks.0.iter() {
+            wanted.push((MarkerKind::Unlocked, region.clone(), "Unlocked".to_string()));
+        }
+        wanted.sort_by(|a, b| (&a.1, a.0).cmp(&(&b.1, b.0)));
+        wanted.dedup();
+        wanted
+    }
+}
+
+/// Respawns the markers whenever what they show changes
+fn sync_markers(
+    mut commands: Commands,
+    graph: Option<Res<RouteGraph>>,
+    regions: Option<Res<MapRegions>>,
+    mut sources: MarkerSources,
+    q_markers: Query<Entity, With<MapMarker>>,
+    mut shown: Local<Vec<(MarkerKind, String, String)>>,
+) {
+    // The graph is inserted again every time the map scene is entered
+    let (Some(graph), Some(regions)) = (graph, regions) else {
+        return;
+    };
+    let wanted = sources.wanted();
+    if !graph.is_changed() && wanted == *shown {
+        return;
+    }
+
+    for marker in q_markers.iter() {
+        commands.entity(marker).despawn();
+    }
+
+    let circle = sources.meshes.add(Circle::new(MARKER_RADIUS));
+    for (region, markers) in wanted.chunk_by(|a, b| a.1 == b.1).map(|m| (&m[0].1, m)) {
+        let Some(anchor) = graph.anchor(region) else {
+            continue;
+        };
+        let icon = regions
+            .defs
+            .iter()
+            .find(|def| def.id == *region)
+            .and_then(|def| def.icon.clone());
+
+        for (i, (kind, _, label)) in markers.iter().enumerate() {
+            let slot = i as f32 - (markers.len() - 1) as f32 / 2.;
+            let marker = commands
+                .spawn((
+                    Mesh2d(circle.clone()),
+                    MeshMaterial2d(sources.materials.add(kind.color())),
+                    Transform::from_translation(anchor.extend(35.)),
+                    Name::new(format!("Marker: {}", label)),
+                    MapMarker {
+                        kind: *kind,
+                        region: region.clone(),
+                        anchor,
+                        offset: Vec2::new(slot * MARKER_SPACING, MARKER_LIFT),
+                    },
+                    MapSceneTag,
+                ))
+                .observe(marker_click)
+                .id();
+
+            // The player's marker shows the region icon when there is one
+            match (&icon, kind) {
+                (Some(icon), MarkerKind::Location) => commands.spawn((
+                    Sprite {
+                        image: sources.asset_server.load(icon),
+                        custom_size: Some(Vec2::splat(MARKER_RADIUS * 1.5)),
+                        ..default()
+                    },
+                    Transform::from_xyz(0., 0., 0.1),
+                    Pickable::IGNORE,
+                    ChildOf(marker),
+                )),
+                _ => commands.spawn((
+                    Text2d::new(kind.glyph()),
+                    TextFont {
+                        font_size: 22.,
+                        ..default()
+                    },
+                    TextColor(Color::BLACK),
+                    Transform::from_xyz(0., 0., 0.1),
+                    Pickable::IGNORE,
+                    ChildOf(marker),
+                )),
+            };
+        }
+    }
+
+    *shown = wanted;
+}
+
+fn map_scale(q_camera: &Query<&Projection, With<PanCam>>) -> Option<f32> {
+    match q_camera.single().ok()? {
+        Projection::Orthographic(projection) => Some(projection.scale),
+        _ => None,
+    }
+}
+
+/**
+Groups regions whose anchors get close on screen.
+
+Every region joins the first group whose first anchor is close enough, groups
+of a single region keep their own markers.
+*/
+fn cluster_markers(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    q_camera: Query<&Projection, With<PanCam>>,
+    mut q_markers: Query<(Entity, &MapMarker, &mut Visibility)>,
+    q_clusters: Query<Entity, With<MarkerCluster>>,
+    mut clustered: Local<Vec<Vec<Entity>>>,
+) {
+    let Some(scale) = map_scale(&q_camera) else {
+        return;
+    };
+
+    let mut groups: Vec<(Vec2, Vec<Entity>)> = Vec::new();
+    let mut sorted: Vec<_> = q_markers
+        .iter()
+        .map(|(entity, marker, _)| (marker.region.clone(), marker.anchor, entity))
+        .collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0).then(a.2.cmp(&b.2)));
+    for (_, anchor, entity) in sorted {
+        let close = groups
+            .iter_mut()
+            .find(|(first, _)| first.distance(anchor) < CLUSTER_DISTANCE * scale);
+        match close {
+            Some((_, members)) => members.push(entity),
+            None => groups.push((anchor, vec![entity])),
+        }
+    }
+
+    let clusters: Vec<Vec<Entity>> = groups
+        .into_iter()
+        .map(|(_, members)| members)
+        .filter(|members| {
+            let first = q_markers
+                .get(members[0])
+                .map(|(_, marker, _)| &marker.region);
+            members
+                .iter()
+                .any(|member| q_markers.get(*member).map(|(_, m, _)| &m.region) != first)
+        })
+        .collect();
+    if clusters == *clustered {
+        return;
+    }
+
+    for cluster in q_clusters.iter() {
+        commands.entity(cluster).despawn();
+    }
+    for (_, _, mut visibility) in q_markers.iter_mut() {
+        *visibility = Visibility::Inherited;
+    }
+
+    let circle = meshes.add(Circle::new(MARKER_RADIUS * 1.4));
+    let material = materials.add(Color::linear_rgb(0.9, 0.9, 0.9));
+    for members in clusters.iter() {
+        let mut regions = Vec::new();
+        let mut centre = Vec2::ZERO;
+        for member in members {
+            let Ok((_, marker, mut visibility)) = q_markers.get_mut(*member) else {
+                continue;
+            };
+            *visibility = Visibility::Hidden;
+            centre += marker.anchor;
+            if !regions.contains(&marker.region) {
+                regions.push(marker.region.clone());
+            }
+        }
+        centre /= members.len() as f32;
+
+        commands
+            .spawn((
+                Mesh2d(circle.clone()),
+                MeshMaterial2d(material.clone()),
+                Transform::from_translation(centre.extend(36.)),
+                Name::new("Marker cluster"),
+                MarkerCluster { regions, centre },
+                MapSceneTag,
+                children![(
+                    Text2d::new(members.len().to_string()),
+                    TextFont {
+                        font_size: 22.,
+                        ..default()
+                    },
+                    TextColor(Color::BLACK),
+                    Transform::from_xyz(0., 0., 0.1),
+                    Pickable::IGNORE,
+                )],
+            ))
+            .observe(cluster_click);
+    }
+
+    *clustered = clusters;
+}
+
+/// Keeps markers the same size on screen whatever the zoom
+fn place_markers(
+    q_camera: Query<&Projection, With<PanCam>>,
+    mut q_markers: Query<(&MapMarker, &mut Transform), Without<MarkerCluster>>,
+    mut q_clusters: Query<(&MarkerCluster, &mut Transform), Without<MapMarker>>,
+) {
+    let Some(scale) = map_scale(&q_camera) else {
+        return;
+    };
+    for (marker, mut transform) in q_markers.iter_mut() {
+        let position = marker.anchor + marker.offset * scale;
+        transform.translation = position.extend(transform.translation.z);
+        transform.scale = Vec3::splat(scale);
+    }
+    for (cluster, mut transform) in q_clusters.iter_mut() {
+        let position = cluster.centre + Vec2::Y * MARKER_LIFT * scale;
+        transform.translation = position.extend(transform.translation.z);
+        transform.scale = Vec3::splat(scale);
+    }
+}
+
+fn marker_click(
+    trigger: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    regions: Option<Res<MapRegions>>,
+    q_markers: Query<&MapMarker>,
+    q_regions: Query<(&RegionId, &RegionBounds)>,
+) {
+    let Ok(marker) = q_markers.get(trigger.target()) else {
+        return;
+    };
+
+    if let Some((_, bounds)) = q_regions.iter().find(|(id, _)| id.0 == marker.region) {
+        commands.trigger(FocusRegion { bounds: bounds.0 });
+    }
+
+    if marker.kind == MarkerKind::Quest {
+        commands.trigger(OpenQuestLog);
+        return;
+    }
+    let def = regions
+        .as_ref()
+        .and_then(|regions| regions.defs.iter().find(|def| def.id == marker.region));
+    if let Some(def) = def {
+        commands.trigger(OpenTravelPrompt {
+            region: def.clone(),
+        });
+    }
+}
+
+/// Zooms in until the regions of the cluster get their own markers
+fn cluster_click(
+    trigger: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    q_clusters: Query<&MarkerCluster>,
+    q_regions: Query<(&RegionId, &RegionBounds)>,
+) {
+    let Ok(cluster) = q_clusters.get(trigger.target()) else {
+        return;
+    };
+    let bounds = q_regions
+        .iter()
+        .filter(|(id, _)| cluster.regions.contains(&id.0))
+        .map(|(_, bounds)| bounds.0)
+        .reduce(|a, b| a.union(b));
+    if let Some(bounds) = bounds {
+        commands.trigger(FocusRegion { bounds });
+    }
+}
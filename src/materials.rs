@@ -0,0 +1,115 @@
+use bevy::pbr::{ExtendedMaterial, MaterialExtension, MaterialPipeline, MaterialPipelineKey};
+use bevy::prelude::*;
+use bevy::render::mesh::MeshVertexBufferLayoutRef;
+use bevy::render::render_resource::{
+    AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+};
+
+/// Quantized lighting with a rim light on top of the standard PBR shading
+pub type CoinMaterial = ExtendedMaterial<StandardMaterial, MyExtendedMaterial>;
+
+/**
+Extension of `StandardMaterial` bound to `my_mat.wgsl`.
+
+The fields share binding 100 and form the shader's `MyExtendedMaterial`
+struct, bindings below 100 are left to the base material. Registered for
+reflection, so the values can be tweaked in the inspector.
+*/
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone)]
+pub struct MyExtendedMaterial {
+    /// Light levels kept per channel
+    #[uniform(100)]
+    pub quantize_steps: u32,
+    #[uniform(100)]
+    pub rim_strength: f32,
+    /// Higher values keep the rim closer to the silhouette
+    #[uniform(100)]
+    pub rim_power: f32,
+}
+
+impl Default for MyExtendedMaterial {
+    fn default() -> Self {
+        MyExtendedMaterial {
+            quantize_steps: 4,
+            rim_strength: 0.3,
+            rim_power: 3.,
+        }
+    }
+}
+
+impl MaterialExtension for MyExtendedMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "my_mat.wgsl".into()
+    }
+}
+
+/**
+Three band cel shading from `toon.wgsl`, for any `Mesh3d` with normals.
+
+Unlit apart from a single light direction, kept in sync with the scene's
+directional light by [`sync_toon_light`].
+*/
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone)]
+pub struct ToonMaterial {
+    /// World space direction pointing towards the light
+    #[uniform(0)]
+    pub light_direction: Vec3,
+    #[uniform(0)]
+    pub base_color: LinearRgba,
+}
+
+impl ToonMaterial {
+    pub fn new(color: Color) -> Self {
+        ToonMaterial {
+            light_direction: Vec3::new(0.5, 1., 1.),
+            base_color: color.into(),
+        }
+    }
+}
+
+impl Material for ToonMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "toon.wgsl".into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        "toon.wgsl".into()
+    }
+
+    /// The shader only reads positions and normals
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout.0.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        Ok(())
+    }
+}
+
+/// Points every [`ToonMaterial`] at the first directional light when it moves or materials are added
+pub fn sync_toon_light(
+    q_light: Query<Ref<GlobalTransform>, With<DirectionalLight>>,
+    mut ev_asset: EventReader<AssetEvent<ToonMaterial>>,
+    mut materials: ResMut<Assets<ToonMaterial>>,
+) {
+    let added = ev_asset
+        .read()
+        .any(|ev| matches!(ev, AssetEvent::Added { .. }));
+    let Some(light) = q_light.iter().next() else {
+        return;
+    };
+    if !added && !light.is_changed() {
+        return;
+    }
+    // Directional lights shine along their forward axis
+    let towards_light = light.back().as_vec3();
+    for (_, material) in materials.iter_mut() {
+        material.light_direction = towards_light;
+    }
+}
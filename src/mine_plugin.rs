@@ -1,21 +1,31 @@
-use crate::states::GameState;
-use crate::util::despawn_screen;
+use crate::big_number::BigNum;
+use crate::drops::{DropKind, DropVisuals, GemKind};
+use crate::economy::{Inventory, Upgrades, Wallet};
+use crate::game_events::{RockDestroyed, RockHit};
+use crate::materials::{CoinMaterial, ToonMaterial, sync_toon_light};
+use crate::mines::{MineBook, MineDef, MineId};
+use crate::prestige::{Prestige, PrestigeReset};
+use crate::ron_asset::RonAssetPlugin;
+use crate::scene_change_plugin::SceneLoading;
+use crate::states::{GameState, SceneTransitionState};
+use crate::util::{Hibernating, Retention, despawn_screen, hibernate_screen, wake_screen};
 use bevy_asset_loader::prelude::*;
 
 use avian3d::prelude::*;
-use bevy::asset::AssetPath;
+use bevy::ecs::system::SystemParam;
 use bevy::render::view::RenderLayers;
-use bevy::{audio::Volume, pbr::OpaqueRendererMethod, prelude::*};
+use bevy::{audio::Volume, prelude::*};
 use bevy_simple_screen_boxing::CameraBox;
 use core::time::Duration;
 use rand::Rng;
 use rand::{seq::SliceRandom, thread_rng};
+use serde::{Deserialize, Serialize};
 use std::ops::{Deref, DerefMut};
 
 #[derive(AssetCollection, Resource)]
 struct SceneAssets {
-    #[asset(path = "private/cave-blue.png")]
-    background: Handle<Image>,
+    #[asset(path = "data/mines.mines.ron")]
+    mines: Handle<MineBook>,
 
     #[asset(path = "private/non-commercial/ambient/music.ogg")]
     ambient: Handle<AudioSource>,
@@ -62,6 +72,9 @@ struct MineSceneTag;
 #[derive(Component)]
 struct Coin {}
 
+#[derive(Component)]
+struct Gem;
+
 #[derive(Component)]
 pub struct BackgroundImg;
 
@@ -81,19 +94,48 @@ impl DerefMut for OriginalTransform {
     }
 }
 
+const CLICK_DAMAGE: f64 = 34.;
+
 #[derive(Component)]
-pub struct Health(f32);
+pub struct Health(BigNum);
+
+impl Default for Health {
+    fn default() -> Self {
+        Health(BigNum::from(100.))
+    }
+}
 
 impl Health {
-    pub fn hit(&mut self, value: f32) {
+    pub fn new(value: BigNum) -> Self {
+        Health(value)
+    }
+
+    pub fn hit(&mut self, value: BigNum) {
         self.0 -= value;
     }
 }
 
 #[derive(Component)]
-#[require(Health(100.))]
+#[require(Health)]
 pub struct Rock;
 
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RockKind {
+    Copper,
+    Silver,
+    Gold,
+}
+
+impl RockKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RockKind::Copper => "copper",
+            RockKind::Silver => "silver",
+            RockKind::Gold => "gold",
+        }
+    }
+}
+
 #[derive(Resource)]
 struct AudioSamples {
     samples: Vec<Handle<AudioSource>>,
@@ -126,29 +168,18 @@ impl Bouncer {
     }
 }
 
-fn mesh_coin_path() -> AssetPath<'static> {
-    GltfAssetLabel::Primitive {
-        mesh: 0,
-        primitive: 0,
-    }
-    .from_asset("private/coin.glb")
-}
-
-#[derive(Resource)]
-struct MyMaterials {
-    coin: Handle<StandardMaterial>,
-}
-
-#[derive(Resource)]
-struct MyHandles {
-    coin: Handle<Mesh>,
-}
-
 pub struct MinePlugin;
 
 impl Plugin for MinePlugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<MyLoadingStates>()
+        app.add_plugins(RonAssetPlugin::<MineBook>::new(&["mines.ron"]))
+            .add_plugins(MaterialPlugin::<CoinMaterial>::default())
+            .register_asset_reflect::<CoinMaterial>()
+            .add_plugins(MaterialPlugin::<ToonMaterial>::default())
+            .register_asset_reflect::<ToonMaterial>()
+            .init_resource::<MineId>()
+            .init_resource::<MineRetention>()
+            .init_state::<MyLoadingStates>()
             .add_loading_state(
                 LoadingState::new(MyLoadingStates::Started)
                     .continue_to_state(MyLoadingStates::Ready)
@@ -156,23 +187,72 @@ impl Plugin for MinePlugin {
             )
             .add_systems(
                 OnEnter(GameState::Mine),
-                |mut next_state: ResMut<NextState<MyLoadingStates>>| {
-                    next_state.set(MyLoadingStates::Started)
-                },
+                (
+                    (
+                        despawn_screen::<MineSceneTag>,
+                        |mut next_state: ResMut<NextState<MyLoadingStates>>| {
+                            next_state.set(MyLoadingStates::Started)
+                        },
+                    )
+                        .run_if(not(can_resume)),
+                    (wake_screen::<MineSceneTag>, await_background)
+                        .chain()
+                        .run_if(can_resume),
+                )
+                    .chain(),
             )
             .add_systems(
                 OnEnter(MyLoadingStates::Ready),
-                (setup, setup_camera, load_gltf),
+                ((setup, await_background).chain(), setup_camera, load_gltf),
             )
             .add_systems(
                 Update,
-                (/*mouse_button_input, */ update, clean_dead)
+                (
+                    /*mouse_button_input, */ update,
+                    clean_dead,
+                    sync_toon_light,
+                )
                     .run_if(in_state(GameState::Mine).and(in_state(MyLoadingStates::Ready))),
             )
-            .add_systems(OnExit(GameState::Mine), despawn_screen::<MineSceneTag>);
+            .add_systems(
+                OnExit(GameState::Mine),
+                (
+                    despawn_screen::<MineSceneTag>.run_if(retention_is(Retention::Despawn)),
+                    hibernate_screen::<MineSceneTag>.run_if(retention_is(Retention::Hibernate)),
+                ),
+            )
+            .add_observer(reset_mine);
     }
 }
 
+/// What happens to the mine when the player leaves it, the map and the tavern are always rebuilt
+#[derive(Resource, Debug)]
+pub struct MineRetention(pub Retention);
+
+impl Default for MineRetention {
+    fn default() -> Self {
+        MineRetention(Retention::Hibernate)
+    }
+}
+
+/// Run condition for the `OnExit` systems of the mine
+fn retention_is(retention: Retention) -> impl Fn(Res<MineRetention>) -> bool + Clone {
+    move |config: Res<MineRetention>| config.0 == retention
+}
+
+/// Mine the scene entities were spawned for
+#[derive(Resource)]
+struct SpawnedMine(String);
+
+/// A hibernated mine is only picked up again if the player came back to the same one
+fn can_resume(
+    mine: Res<MineId>,
+    spawned: Option<Res<SpawnedMine>>,
+    q_hibernating: Query<(), (With<MineSceneTag>, With<Hibernating>)>,
+) -> bool {
+    !q_hibernating.is_empty() && spawned.is_some_and(|spawned| spawned.0 == mine.0)
+}
+
 fn setup_camera(mut commands: Commands) {
     commands.spawn((
         Camera2d,
@@ -212,76 +292,47 @@ fn setup_camera(mut commands: Commands) {
     ));
 }
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>, assets: Res<SceneAssets>) {
+/// Definition of the mine the player travelled to, once the scene assets are loaded
+#[derive(SystemParam)]
+struct CurrentMine<'w> {
+    assets: Option<Res<'w, SceneAssets>>,
+    books: Res<'w, Assets<MineBook>>,
+    mine: Res<'w, MineId>,
+}
+
+impl CurrentMine<'_> {
+    fn def(&self) -> Option<&MineDef> {
+        self.books
+            .get(&self.assets.as_ref()?.mines)?
+            .get(&self.mine)
+    }
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>, current: CurrentMine) {
+    let (Some(assets), Some(mine)) = (current.assets.as_ref(), current.def()) else {
+        warn!("No mine definitions loaded");
+        return;
+    };
+    commands.insert_resource(SpawnedMine(mine.id.clone()));
+
+    let (r, g, b) = mine.tint;
     commands.spawn((
         Sprite {
-            image: assets.background.clone(),
+            image: asset_server.load(mine.background.clone()),
             //image_mode: SpriteImageMode::Scale(ScalingMode::FillStart),
             custom_size: Some(Vec2::new(1920., 1080.)),
+            color: Color::srgb(r, g, b),
+            flip_x: mine.flip_background,
             ..default()
         },
-        Name::new("Background"),
+        Name::new(format!("{} background", mine.name)),
         Transform::from_xyz(0., 0., 1.),
         Pickable::default(),
         BackgroundImg,
         MineSceneTag,
     ));
 
-    let [rock1, rock2, rock3, rock4]: [Handle<Image>; 4] = (0..4)
-        .map(|i| assets.rocks.get(i).unwrap().clone())
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap();
-
-    commands
-        .spawn((
-            Sprite::from_image(rock1),
-            OriginalTransform(Transform::from_xyz(120., 50., 2.).with_scale(Vec3::splat(0.75))),
-            Rock,
-            Bouncer::default(),
-            Pickable::default(),
-            MineSceneTag,
-        ))
-        .observe(rock_click);
-
-    commands
-        .spawn((
-            Sprite::from_image(rock2),
-            OriginalTransform(Transform::from_xyz(100., -50., 3.).with_scale(Vec3::splat(0.75))),
-            Rock,
-            Bouncer::default(),
-            Pickable::default(),
-            MineSceneTag,
-        ))
-        .observe(rock_click);
-
-    commands
-        .spawn((
-            Sprite::from_image(rock3),
-            OriginalTransform(Transform::from_xyz(-150., 0., 4.).with_scale(Vec3::splat(0.75))),
-            Rock,
-            Bouncer::default(),
-            Pickable::default(),
-            MineSceneTag,
-        ))
-        .observe(rock_click);
-
-    commands
-        .spawn((
-            Sprite {
-                image: rock4,
-                //anchor: bevy::sprite::Anchor::TopLeft,
-                ..default()
-            },
-            OriginalTransform(
-                Transform::from_xyz(-1920. / 2. + 100., 0., 5.).with_scale(Vec3::splat(0.745)),
-            ),
-            Rock,
-            Bouncer::default(),
-            Pickable::default(),
-            MineSceneTag,
-        ))
-        .observe(rock_click);
+    spawn_rocks(&mut commands, assets, mine);
 
     commands.spawn((
         AudioPlayer::new(assets.ambient.clone()),
@@ -294,18 +345,64 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>, assets: Res<Sce
     ));
 }
 
+/// The background covers the whole scene, it has to be there before the fade in
+fn await_background(mut commands: Commands, q_background: Query<&Sprite, With<BackgroundImg>>) {
+    commands.insert_resource(SceneLoading {
+        scene: GameState::Mine,
+        handles: q_background
+            .iter()
+            .map(|sprite| sprite.image.clone().untyped())
+            .collect(),
+    });
+}
+
+fn spawn_rocks(commands: &mut Commands, assets: &SceneAssets, mine: &MineDef) {
+    for (i, rock) in mine.rocks.iter().enumerate() {
+        let Some(image) = assets.rocks.get(rock.layer) else {
+            warn!("Mine {} uses missing rock layer {}", mine.id, rock.layer);
+            continue;
+        };
+
+        commands
+            .spawn((
+                Sprite::from_image(image.clone()),
+                OriginalTransform(
+                    Transform::from_xyz(rock.position.0, rock.position.1, 2. + i as f32)
+                        .with_scale(Vec3::splat(rock.scale)),
+                ),
+                Rock,
+                rock.kind,
+                Health::new(BigNum::from(100.) * mine.hp_scale),
+                Bouncer::default(),
+                Pickable::default(),
+                MineSceneTag,
+            ))
+            .observe(rock_click);
+    }
+}
+
 fn rock_click(
     trigger: Trigger<Pointer<Click>>,
     mut entities: Query<(&mut Bouncer, &mut Health)>,
     mut commands: Commands,
     assets: Res<SceneAssets>,
+    upgrades: Res<Upgrades>,
+    prestige: Res<Prestige>,
+    transition: Res<State<SceneTransitionState>>,
 ) {
+    // The mine is being left
+    if *transition.get() != SceneTransitionState::Normal {
+        return;
+    }
     let entity = entities.get_mut(trigger.target());
     if entity.is_ok() {
         let entity = entity.unwrap();
         let (mut bouncer, mut health) = entity;
         bouncer.bounce();
-        health.hit(34.);
+        let damage =
+            BigNum::from(CLICK_DAMAGE) * (upgrades.damage_multiplier() * prestige.multiplier());
+        health.hit(damage);
+        commands.trigger(RockHit { damage });
         let sample = assets.hits.choose(&mut thread_rng()).unwrap();
         commands.spawn((
             AudioPlayer::new(sample.clone()),
@@ -322,7 +419,10 @@ fn rock_click(
 fn load_gltf(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    visuals: Option<Res<DropVisuals>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut coin_materials: ResMut<Assets<CoinMaterial>>,
+    mut toon_materials: ResMut<Assets<ToonMaterial>>,
 ) {
     commands.spawn((
         DirectionalLight {
@@ -353,55 +453,66 @@ fn load_gltf(
 
     //let obj: Handle<Mesh> = asset_server.load("private/coin.obj");
 
-    let mesh_handle: Handle<Mesh> = asset_server.load(mesh_coin_path());
-    commands.insert_resource(MyHandles {
-        coin: mesh_handle.clone(),
-    });
-
-    let new_mat = StandardMaterial {
-        base_color: Color::linear_rgb(1.0, 0.75, 0.1),
-        perceptual_roughness: 0.25,
-        metallic: 0.96,
-        reflectance: 0.96,
-
-        opaque_render_method: OpaqueRendererMethod::Auto,
-        ..Default::default()
-    };
-
-    let mat = materials.add(new_mat);
-
-    commands.insert_resource(MyMaterials { coin: mat.clone() });
+    if visuals.is_none() {
+        commands.insert_resource(DropVisuals::new(
+            &asset_server,
+            &mut meshes,
+            &mut coin_materials,
+            &mut toon_materials,
+        ));
+    }
 }
 
-fn spawn_coins(
-    commands: &mut Commands,
-    asset_server: &Res<AssetServer>,
-    materials: &Res<MyMaterials>,
-    at: Vec2,
-) {
-    let mesh: Handle<Mesh> = asset_server.get_handle(mesh_coin_path()).expect("No mesh");
-    println!("{:?}", at);
+fn spawn_coins(commands: &mut Commands, visuals: &DropVisuals, kind: RockKind, at: Vec2) -> u64 {
+    let Some(visual) = visuals.get(DropKind::Coin(kind)) else {
+        return 0;
+    };
 
+    let mut spawned = 0;
     for x in 1..=6 {
         for y in 1..=6 {
             let r1 = thread_rng().gen_range(-1.0..1.0);
             let r2 = thread_rng().gen_range(-1.0..1.0);
             let r3 = thread_rng().gen_range(-1.0..1.0);
-            commands.spawn((
+            let mut coin = commands.spawn((
                 Coin {},
-                Mesh3d(mesh.clone()),
                 RigidBody::Dynamic,
                 AngularVelocity(Vec3::new(r1 * 2., r2 * 2., r3 * 2.)),
                 LinearVelocity(Vec3::new(r1 * 3., 2.5 + r2, 10. + r3)),
                 Collider::sphere(0.1),
-                MeshMaterial3d(materials.coin.clone()),
                 Transform::from_translation((at / 20.).extend(-20. + x as f32 + y as f32))
                     .looking_at(Vec3::ZERO, Vec3::X),
                 RenderLayers::layer(1),
                 MineSceneTag,
             ));
+            visual.apply(&mut coin);
+            spawned += 1;
         }
     }
+
+    spawned
+}
+
+/// Gems pop out a little higher than coins so they stand out
+fn spawn_gems(commands: &mut Commands, visuals: &DropVisuals, kind: GemKind, count: u32, at: Vec2) {
+    let Some(visual) = visuals.get(DropKind::Gem(kind)) else {
+        return;
+    };
+    for _ in 0..count {
+        let r1 = thread_rng().gen_range(-1.0..1.0);
+        let r2 = thread_rng().gen_range(-1.0..1.0);
+        let mut gem = commands.spawn((
+            Gem,
+            RigidBody::Dynamic,
+            AngularVelocity(Vec3::new(r2 * 3., r1 * 3., 0.)),
+            LinearVelocity(Vec3::new(r1 * 2., 5. + r2, 10.)),
+            Collider::sphere(0.12),
+            Transform::from_translation((at / 20.).extend(-10.)),
+            RenderLayers::layer(1),
+            MineSceneTag,
+        ));
+        visual.apply(&mut gem);
+    }
 }
 
 fn update(
@@ -421,19 +532,30 @@ fn update(
 
 fn clean_dead(
     mut commands: Commands,
-    q: Query<(Entity, &Health, &Transform)>,
-    asset_server: Res<AssetServer>,
-    materials: Res<MyMaterials>,
-    assets: Res<SceneAssets>,
+    q: Query<(Entity, &Health, &Transform, &RockKind)>,
+    visuals: Option<Res<DropVisuals>>,
+    current: CurrentMine,
+    mut wallet: ResMut<Wallet>,
+    mut inventory: ResMut<Inventory>,
+    prestige: Res<Prestige>,
 ) {
-    for (entity, hp, tr) in q.iter() {
-        if hp.0 < 0. {
-            spawn_coins(
-                &mut commands,
-                &asset_server,
-                &materials,
-                tr.translation.truncate(),
-            );
+    let mine = current.def();
+    let (Some(visuals), Some(assets)) = (visuals, current.assets.as_ref()) else {
+        return;
+    };
+    for (entity, hp, tr, kind) in q.iter() {
+        if hp.0.is_negative() {
+            let at = tr.translation.truncate();
+            let coins = spawn_coins(&mut commands, &visuals, *kind, at);
+            let coin_multiplier = mine.map_or(1., |mine| mine.coin_multiplier);
+            wallet.earn((BigNum::from(coins) * (coin_multiplier * prestige.multiplier())).floor());
+            for (item, count) in mine.map(MineDef::roll_loot).unwrap_or_default() {
+                inventory.add(item, count);
+                if let Some(gem) = GemKind::from_item(item) {
+                    spawn_gems(&mut commands, &visuals, gem, count, at);
+                }
+            }
+            commands.trigger(RockDestroyed { kind: *kind });
             commands.spawn((
                 AudioPlayer::new(assets.money_spill.clone()),
                 PlaybackSettings {
@@ -448,6 +570,33 @@ fn clean_dead(
     }
 }
 
+/// Everything a prestige reset wipes from the mine
+type MineProgress = Or<(With<Rock>, With<Coin>, With<Gem>)>;
+
+/// Prestige wipes the mine, rocks grow back right away when the player is in it
+fn reset_mine(
+    _trigger: Trigger<PrestigeReset>,
+    mut commands: Commands,
+    q_progress: Query<Entity, MineProgress>,
+    q_hibernating: Query<Entity, (With<MineSceneTag>, With<Hibernating>)>,
+    state: Res<State<GameState>>,
+    current: CurrentMine,
+) {
+    for entity in q_progress.iter() {
+        commands.entity(entity).despawn();
+    }
+    // A hibernated mine would come back empty, it is rebuilt on the next visit instead
+    for entity in q_hibernating.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    if let (GameState::Mine, Some(assets), Some(mine)) =
+        (state.get(), current.assets.as_ref(), current.def())
+    {
+        spawn_rocks(&mut commands, assets, mine);
+    }
+}
+
 fn rotate_coin(time: Res<Time>, mut transform_q: Query<&mut Transform, With<Coin>>) {
     let delta = time.delta();
 
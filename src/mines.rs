@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::mine_plugin::RockKind;
+
+/// Mine the player travelled to, picked on the map and persisted with the save
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MineId(pub String);
+
+impl Default for MineId {
+    fn default() -> Self {
+        MineId("copper_hills".to_string())
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RockSpawn {
+    pub kind: RockKind,
+    /// Index into the rock layer images
+    pub layer: usize,
+    pub position: (f32, f32),
+    pub scale: f32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LootDrop {
+    pub item: String,
+    /// Chance per broken rock, `0..=1`
+    pub chance: f32,
+    pub count: u32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MineDef {
+    pub id: String,
+    pub name: String,
+    pub background: String,
+    /// Multiplies the background colours, mines sharing an image still look apart
+    #[serde(default = "no_tint")]
+    pub tint: (f32, f32, f32),
+    #[serde(default)]
+    pub flip_background: bool,
+    /// Multiplies the base rock health
+    pub hp_scale: f64,
+    /// Multiplies the coins spilled by broken rocks
+    pub coin_multiplier: f64,
+    pub rocks: Vec<RockSpawn>,
+    #[serde(default)]
+    pub loot: Vec<LootDrop>,
+}
+
+fn no_tint() -> (f32, f32, f32) {
+    (1., 1., 1.)
+}
+
+impl MineDef {
+    /// Rolls the loot table once, for a single broken rock
+    pub fn roll_loot(&self) -> Vec<(&str, u32)> {
+        let mut rng = rand::thread_rng();
+        self.loot
+            .iter()
+            .filter(|drop| rng.gen_range(0.0..1.0) < drop.chance)
+            .map(|drop| (drop.item.as_str(), drop.count))
+            .collect()
+    }
+}
+
+/**
+Every mine of the game, loaded from `data/mines.mines.ron`.
+
+The Mine scene is the same for all of them, [`MineId`] decides which
+definition it is built from.
+*/
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct MineBook {
+    pub mines: Vec<MineDef>,
+}
+
+impl MineBook {
+    /// Falls back to the first mine so a stale id never leaves the scene empty
+    pub fn get(&self, id: &MineId) -> Option<&MineDef> {
+        self.mines
+            .iter()
+            .find(|mine| mine.id == id.0)
+            .or_else(|| self.mines.first())
+    }
+}
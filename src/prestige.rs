@@ -0,0 +1,227 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::big_number::BigNum;
+use crate::economy::{Inventory, Upgrades, Wallet};
+use crate::scene_change_plugin::not_in_transition;
+use crate::states::{GameState, SceneTransitionState};
+
+/// Coins that have to be earned in a single run before prestige is possible
+pub const PRESTIGE_THRESHOLD: f64 = 10_000.;
+
+pub struct PrestigePlugin;
+
+/// Permanent progress that survives resets, persisted with the save
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Prestige {
+    pub points: u64,
+    pub resets: u32,
+}
+
+impl Prestige {
+    /// Applies to click damage and coins gained
+    pub fn multiplier(&self) -> f64 {
+        1. + 0.1 * self.points as f64
+    }
+
+    pub fn points_for(earned: BigNum) -> u64 {
+        let threshold = BigNum::from(PRESTIGE_THRESHOLD);
+        if earned < threshold {
+            return 0;
+        }
+        // Saturates for absurdly long runs
+        (earned / threshold).to_f64().sqrt() as u64
+    }
+}
+
+/// Triggered after the run has been reset, scenes drop their progress on it
+#[derive(Event, Debug, Clone)]
+pub struct PrestigeReset;
+
+#[derive(Event, Debug, Clone)]
+pub struct OpenPrestigeScreen;
+
+#[derive(Component)]
+struct PrestigeScreen;
+
+impl Plugin for PrestigePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Prestige>()
+            .add_observer(open_screen)
+            .add_systems(
+                Update,
+                (|mut commands: Commands| commands.trigger(OpenPrestigeScreen))
+                    .run_if(bevy::input::common_conditions::input_just_pressed(
+                        KeyCode::KeyP,
+                    ))
+                    // Not from the map, a trip there would outlive the reset
+                    .run_if(in_state(GameState::Mine).or(in_state(GameState::Tavern)))
+                    .run_if(not_in_transition),
+            );
+    }
+}
+
+fn open_screen(
+    _trigger: Trigger<OpenPrestigeScreen>,
+    mut commands: Commands,
+    wallet: Res<Wallet>,
+    prestige: Res<Prestige>,
+    q_screen: Query<(), With<PrestigeScreen>>,
+) {
+    if !q_screen.is_empty() {
+        return;
+    }
+
+    let gained = Prestige::points_for(wallet.earned);
+    let summary = if gained == 0 {
+        format!(
+            "Earn {} coins in this run to prestige ({} so far).",
+            BigNum::from(PRESTIGE_THRESHOLD),
+            wallet.earned
+        )
+    } else {
+        let after = Prestige {
+            points: prestige.points + gained,
+            ..default()
+        };
+        format!(
+            "Gain {} prestige points, multiplier x{:.1} -> x{:.1}\n\n\
+            Lost: coins, items, upgrades, mine progress\n\
+            Kept: prestige points, achievements, statistics, contracts,\n\
+            unlocked regions, your place on the map and the chosen mine",
+            gained,
+            prestige.multiplier(),
+            after.multiplier()
+        )
+    };
+
+    // Full screen so nothing behind can be clicked while deciding
+    let screen = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::linear_rgba(0., 0., 0., 0.7)),
+            GlobalZIndex(15),
+            Name::new("Prestige"),
+            PrestigeScreen,
+        ))
+        .id();
+
+    let panel = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(16.),
+                padding: UiRect::all(Val::Px(24.)),
+                ..default()
+            },
+            BackgroundColor(Color::linear_rgb(0.15, 0.1, 0.2)),
+            ChildOf(screen),
+            children![
+                (
+                    Text::new("Prestige"),
+                    TextFont {
+                        font_size: 36.,
+                        ..default()
+                    },
+                ),
+                Text::new(summary),
+            ],
+        ))
+        .id();
+
+    let buttons = commands
+        .spawn((
+            Node {
+                column_gap: Val::Px(16.),
+                ..default()
+            },
+            ChildOf(panel),
+        ))
+        .id();
+
+    if gained > 0 {
+        commands
+            .spawn((
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(16.), Val::Px(8.)),
+                    ..default()
+                },
+                BackgroundColor(Color::linear_rgb(0.5, 0.2, 0.6)),
+                ChildOf(buttons),
+                children![(Text::new("Prestige"), Pickable::IGNORE)],
+            ))
+            .observe(confirm);
+    }
+
+    commands
+        .spawn((
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(16.), Val::Px(8.)),
+                ..default()
+            },
+            BackgroundColor(Color::linear_rgb(0.3, 0.3, 0.3)),
+            ChildOf(buttons),
+            children![(Text::new("Cancel"), Pickable::IGNORE)],
+        ))
+        .observe(
+            |_trigger: Trigger<Pointer<Click>>,
+             mut commands: Commands,
+             q_screen: Query<Entity, With<PrestigeScreen>>| {
+                for screen in q_screen.iter() {
+                    commands.entity(screen).despawn();
+                }
+            },
+        );
+}
+
+/// What a prestige reset takes away
+#[derive(SystemParam)]
+struct Run<'w> {
+    wallet: ResMut<'w, Wallet>,
+    inventory: ResMut<'w, Inventory>,
+    upgrades: ResMut<'w, Upgrades>,
+}
+
+impl Run<'_> {
+    fn reset(&mut self) {
+        *self.wallet = default();
+        *self.inventory = default();
+        *self.upgrades = default();
+    }
+}
+
+fn confirm(
+    _trigger: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    mut prestige: ResMut<Prestige>,
+    mut run: Run,
+    transition: Res<State<SceneTransitionState>>,
+    q_screen: Query<Entity, With<PrestigeScreen>>,
+) {
+    // The scene is being swapped, wait until it has settled
+    if *transition.get() != SceneTransitionState::Normal {
+        return;
+    }
+    let gained = Prestige::points_for(run.wallet.earned);
+    if gained > 0 {
+        prestige.points += gained;
+        prestige.resets += 1;
+        run.reset();
+        commands.trigger(PrestigeReset);
+    }
+
+    for screen in q_screen.iter() {
+        commands.entity(screen).despawn();
+    }
+}
@@ -0,0 +1,354 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::achievements::{ToastStack, spawn_toast};
+use crate::big_number::BigNum;
+use crate::economy::{Inventory, Wallet};
+use crate::game_events::{ItemCrafted, RegionVisited, RockDestroyed};
+use crate::mine_plugin::RockKind;
+use crate::ron_asset::RonAssetPlugin;
+use crate::scene_change_plugin::not_in_transition;
+
+pub struct QuestPlugin;
+
+/// What has to be done to fulfill a contract
+#[derive(Deserialize, Debug, Clone)]
+pub enum Objective {
+    BreakRocks { kind: Option<RockKind>, count: u32 },
+    CraftItems { item: String, count: u32 },
+    VisitRegion { region: String },
+}
+
+impl Objective {
+    pub fn target(&self) -> u32 {
+        match self {
+            Objective::BreakRocks { count, .. } => *count,
+            Objective::CraftItems { count, .. } => *count,
+            Objective::VisitRegion { .. } => 1,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Objective::BreakRocks {
+                kind: Some(kind),
+                count,
+            } => format!("Break {} {} rocks", count, kind.name()),
+            Objective::BreakRocks { kind: None, count } => format!("Break {} rocks", count),
+            Objective::CraftItems { item, count } => format!("Deliver {} x {}", count, item),
+            Objective::VisitRegion { region } => format!("Visit {}", region),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Reward {
+    pub coins: BigNum,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct QuestDef {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub objective: Objective,
+    pub reward: Reward,
+    /// Map region the contract is pinned to
+    #[serde(default)]
+    pub region: Option<String>,
+}
+
+impl QuestDef {
+    /// Visit objectives are pinned to the region to visit when no region is given
+    pub fn region(&self) -> Option<&str> {
+        match (&self.region, &self.objective) {
+            (Some(region), _) => Some(region),
+            (None, Objective::VisitRegion { region }) => Some(region),
+            _ => None,
+        }
+    }
+}
+
+/// Every contract the game knows about, loaded from `data/*.quests.ron`
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct QuestBook {
+    pub quests: Vec<QuestDef>,
+}
+
+impl QuestBook {
+    pub fn get(&self, id: &str) -> Option<&QuestDef> {
+        self.quests.iter().find(|quest| quest.id == id)
+    }
+}
+
+#[derive(Resource)]
+pub struct Quests {
+    pub book: Handle<QuestBook>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QuestProgress {
+    pub id: String,
+    pub progress: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuestStatus {
+    Available,
+    Active(u32),
+    Completed,
+}
+
+/// Player's progress on contracts, persisted with the save
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone)]
+pub struct QuestLog {
+    pub active: Vec<QuestProgress>,
+    pub completed: Vec<String>,
+}
+
+impl QuestLog {
+    pub fn status(&self, id: &str) -> QuestStatus {
+        if self.completed.iter().any(|done| done == id) {
+            return QuestStatus::Completed;
+        }
+        match self.active.iter().find(|quest| quest.id == id) {
+            Some(quest) => QuestStatus::Active(quest.progress),
+            None => QuestStatus::Available,
+        }
+    }
+
+    /// Returns `false` if the quest was already taken or finished
+    pub fn accept(&mut self, id: &str) -> bool {
+        if self.status(id) != QuestStatus::Available {
+            return false;
+        }
+        self.active.push(QuestProgress {
+            id: id.to_string(),
+            progress: 0,
+        });
+        true
+    }
+
+    fn complete(&mut self, id: &str) {
+        self.active.retain(|quest| quest.id != id);
+        self.completed.push(id.to_string());
+    }
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct QuestCompleted {
+    pub id: String,
+}
+
+#[derive(SystemParam)]
+struct QuestTracker<'w, 's> {
+    commands: Commands<'w, 's>,
+    log: ResMut<'w, QuestLog>,
+    wallet: ResMut<'w, Wallet>,
+    inventory: ResMut<'w, Inventory>,
+    quests: Res<'w, Quests>,
+    books: Res<'w, Assets<QuestBook>>,
+}
+
+impl QuestTracker<'_, '_> {
+    /// `step` tells how much a game event moves the given objective forward
+    fn advance(&mut self, step: impl Fn(&Objective) -> u32) {
+        let Some(book) = self.books.get(&self.quests.book) else {
+            return;
+        };
+
+        let mut finished = Vec::new();
+        for quest in self.log.active.iter_mut() {
+            let Some(def) = book.get(&quest.id) else {
+                continue;
+            };
+            let amount = step(&def.objective);
+            if amount == 0 {
+                continue;
+            }
+            quest.progress = (quest.progress + amount).min(def.objective.target());
+            if quest.progress >= def.objective.target() {
+                finished.push(def);
+            }
+        }
+
+        for def in finished {
+            // Goods are handed over on delivery, the contract waits until there are enough
+            if let Objective::CraftItems { item, count } = &def.objective
+                && !self.inventory.take(item, *count)
+            {
+                continue;
+            }
+            self.log.complete(&def.id);
+            self.wallet.earn(def.reward.coins);
+            self.commands.trigger(QuestCompleted { id: def.id.clone() });
+        }
+    }
+}
+
+/// Shows the contracts panel if it isn't open yet
+#[derive(Event, Debug, Clone)]
+pub struct OpenQuestLog;
+
+#[derive(Component)]
+struct QuestLogPanel;
+
+#[derive(Component)]
+struct QuestLogText;
+
+impl Plugin for QuestPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<QuestBook>::new(&["quests.ron"]))
+            .init_resource::<QuestLog>()
+            .add_systems(Startup, load_quests)
+            .add_observer(on_rock_destroyed)
+            .add_observer(on_item_crafted)
+            .add_observer(on_region_visited)
+            .add_observer(on_quest_completed)
+            .add_observer(
+                |_trigger: Trigger<OpenQuestLog>,
+                 mut commands: Commands,
+                 q_panel: Query<(), With<QuestLogPanel>>| {
+                    if q_panel.is_empty() {
+                        spawn_quest_log(&mut commands);
+                    }
+                },
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_quest_log
+                        .run_if(bevy::input::common_conditions::input_just_pressed(
+                            KeyCode::KeyJ,
+                        ))
+                        .run_if(not_in_transition),
+                    update_quest_log,
+                ),
+            );
+    }
+}
+
+fn load_quests(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Quests {
+        book: asset_server.load("data/contracts.quests.ron"),
+    });
+}
+
+fn on_rock_destroyed(trigger: Trigger<RockDestroyed>, mut tracker: QuestTracker) {
+    let destroyed = trigger.event().kind;
+    tracker.advance(|objective| match objective {
+        Objective::BreakRocks { kind, .. } if kind.is_none_or(|kind| kind == destroyed) => 1,
+        _ => 0,
+    });
+}
+
+fn on_item_crafted(trigger: Trigger<ItemCrafted>, mut tracker: QuestTracker) {
+    let crafted = trigger.event();
+    tracker.advance(|objective| match objective {
+        Objective::CraftItems { item, .. } if *item == crafted.item => crafted.count,
+        _ => 0,
+    });
+}
+
+fn on_region_visited(trigger: Trigger<RegionVisited>, mut tracker: QuestTracker) {
+    let visited = &trigger.event().region;
+    tracker.advance(|objective| match objective {
+        Objective::VisitRegion { region } if region == visited => 1,
+        _ => 0,
+    });
+}
+
+fn on_quest_completed(
+    trigger: Trigger<QuestCompleted>,
+    mut commands: Commands,
+    quests: Res<Quests>,
+    books: Res<Assets<QuestBook>>,
+    q_stack: Query<Entity, With<ToastStack>>,
+) {
+    let Some(def) = books
+        .get(&quests.book)
+        .and_then(|book| book.get(&trigger.event().id))
+    else {
+        return;
+    };
+    if let Ok(stack) = q_stack.single() {
+        spawn_toast(
+            &mut commands,
+            stack,
+            format!("Contract completed: {}", def.title),
+            format!("Reward: {} coins", def.reward.coins),
+        );
+    }
+}
+
+fn toggle_quest_log(mut commands: Commands, q_panel: Query<Entity, With<QuestLogPanel>>) {
+    if let Ok(panel) = q_panel.single() {
+        commands.entity(panel).despawn();
+        return;
+    }
+    spawn_quest_log(&mut commands);
+}
+
+fn spawn_quest_log(commands: &mut Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(20.),
+            right: Val::Px(20.),
+            width: Val::Px(420.),
+            padding: UiRect::all(Val::Px(12.)),
+            ..default()
+        },
+        BackgroundColor(Color::linear_rgba(0.05, 0.03, 0.02, 0.85)),
+        Pickable::IGNORE,
+        Name::new("Quest log"),
+        QuestLogPanel,
+        children![(
+            Text::default(),
+            TextFont {
+                font_size: 18.,
+                ..default()
+            },
+            Pickable::IGNORE,
+            QuestLogText,
+        )],
+    ));
+}
+
+fn update_quest_log(
+    log: Res<QuestLog>,
+    quests: Res<Quests>,
+    books: Res<Assets<QuestBook>>,
+    mut q_text: Query<(&mut Text, Ref<QuestLogText>)>,
+) {
+    let Ok((mut text, tag)) = q_text.single_mut() else {
+        return;
+    };
+    if !tag.is_added() && !log.is_changed() && !books.is_changed() {
+        return;
+    }
+    let Some(book) = books.get(&quests.book) else {
+        return;
+    };
+
+    let mut lines = vec!["Contracts (J to close)".to_string(), String::new()];
+    if log.active.is_empty() {
+        lines.push("No active contracts, visit the Tavern".to_string());
+    }
+    for quest in log.active.iter() {
+        if let Some(def) = book.get(&quest.id) {
+            lines.push(format!(
+                "{}: {} {}/{}",
+                def.title,
+                def.objective.describe(),
+                quest.progress,
+                def.objective.target()
+            ));
+        }
+    }
+    lines.push(String::new());
+    lines.push(format!("Completed: {}", log.completed.len()));
+
+    text.0 = lines.join("\n");
+}
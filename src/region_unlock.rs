@@ -0,0 +1,221 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use crate::economy::Wallet;
+use crate::game_events::RegionUnlocked;
+use crate::map_legend::RegionDef;
+use crate::quest::{QuestLog, QuestStatus};
+use crate::states::GameState;
+use crate::util::despawn_screen;
+
+pub struct RegionUnlockPlugin;
+
+/// Regions the player paid for, persisted with the save
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone)]
+pub struct RegionUnlocks {
+    pub unlocked: BTreeSet<String>,
+}
+
+impl RegionUnlocks {
+    /// Regions without requirements are open from the start
+    pub fn is_unlocked(&self, region: &RegionDef) -> bool {
+        !region.has_requirements() || self.unlocked.contains(&region.id)
+    }
+}
+
+impl RegionDef {
+    pub fn has_requirements(&self) -> bool {
+        self.unlock_cost.is_some() || self.unlock_quest.is_some()
+    }
+
+    /// One line per requirement, with whether the player meets it
+    pub fn requirements(&self, wallet: &Wallet, quests: &QuestLog) -> Vec<(String, bool)> {
+        let mut requirements = Vec::new();
+        if let Some(quest) = &self.unlock_quest {
+            requirements.push((
+                format!("Complete the {} contract", quest),
+                quests.status(quest) == QuestStatus::Completed,
+            ));
+        }
+        if let Some(cost) = self.unlock_cost {
+            requirements.push((format!("Pay {} coins", cost), wallet.coins >= cost));
+        }
+        requirements
+    }
+}
+
+/// Shows what a locked region needs, and unlocks it if everything is met
+#[derive(Event, Debug, Clone)]
+pub struct OpenUnlockPrompt {
+    pub region: RegionDef,
+}
+
+#[derive(Component)]
+struct UnlockPrompt;
+
+impl Plugin for RegionUnlockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RegionUnlocks>()
+            .add_observer(open_prompt)
+            .add_systems(OnExit(GameState::Map), despawn_screen::<UnlockPrompt>);
+    }
+}
+
+fn open_prompt(
+    trigger: Trigger<OpenUnlockPrompt>,
+    mut commands: Commands,
+    wallet: Res<Wallet>,
+    quests: Res<QuestLog>,
+    q_prompt: Query<Entity, With<UnlockPrompt>>,
+) {
+    for prompt in q_prompt.iter() {
+        commands.entity(prompt).despawn();
+    }
+
+    let region = trigger.event().region.clone();
+    let requirements = region.requirements(&wallet, &quests);
+    let ready = requirements.iter().all(|(_, met)| *met);
+
+    let screen = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::linear_rgba(0., 0., 0., 0.5)),
+            GlobalZIndex(10),
+            Name::new("Unlock"),
+            UnlockPrompt,
+        ))
+        .id();
+
+    let panel = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(16.),
+                padding: UiRect::all(Val::Px(24.)),
+                ..default()
+            },
+            BackgroundColor(Color::linear_rgb(0.1, 0.15, 0.2)),
+            ChildOf(screen),
+            children![
+                (
+                    Text::new(format!("{} (locked)", region.name)),
+                    TextFont {
+                        font_size: 36.,
+                        ..default()
+                    },
+                ),
+                Text::new("Requirements"),
+            ],
+        ))
+        .id();
+
+    for (text, met) in requirements {
+        let color = if met {
+            Color::linear_rgb(0.4, 0.9, 0.4)
+        } else {
+            Color::linear_rgb(0.9, 0.4, 0.4)
+        };
+        commands.spawn((
+            Text::new(format!("{} {}", if met { "[x]" } else { "[ ]" }, text)),
+            TextColor(color),
+            ChildOf(panel),
+        ));
+    }
+
+    let buttons = commands
+        .spawn((
+            Node {
+                column_gap: Val::Px(16.),
+                ..default()
+            },
+            ChildOf(panel),
+        ))
+        .id();
+
+    if ready {
+        commands
+            .spawn((
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(16.), Val::Px(8.)),
+                    ..default()
+                },
+                BackgroundColor(Color::linear_rgb(0.2, 0.5, 0.3)),
+                ChildOf(buttons),
+                children![(Text::new("Unlock"), Pickable::IGNORE)],
+            ))
+            .observe(
+                move |_trigger: Trigger<Pointer<Click>>,
+                      mut commands: Commands,
+                      mut wallet: ResMut<Wallet>,
+                      mut unlocks: ResMut<RegionUnlocks>,
+                      quests: Res<QuestLog>,
+                      q_prompt: Query<Entity, With<UnlockPrompt>>| {
+                    for prompt in q_prompt.iter() {
+                        commands.entity(prompt).despawn();
+                    }
+                    unlock(&mut commands, &region, &mut wallet, &mut unlocks, &quests);
+                },
+            );
+    }
+
+    commands
+        .spawn((
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(16.), Val::Px(8.)),
+                ..default()
+            },
+            BackgroundColor(Color::linear_rgb(0.3, 0.3, 0.3)),
+            ChildOf(buttons),
+            children![(Text::new("Cancel"), Pickable::IGNORE)],
+        ))
+        .observe(
+            |_trigger: Trigger<Pointer<Click>>,
+             mut commands: Commands,
+             q_prompt: Query<Entity, With<UnlockPrompt>>| {
+                for prompt in q_prompt.iter() {
+                    commands.entity(prompt).despawn();
+                }
+            },
+        );
+}
+
+/// Requirements are checked again, the wallet may have changed while the prompt was open
+fn unlock(
+    commands: &mut Commands,
+    region: &RegionDef,
+    wallet: &mut Wallet,
+    unlocks: &mut RegionUnlocks,
+    quests: &QuestLog,
+) {
+    if unlocks.is_unlocked(region) {
+        return;
+    }
+    if !region
+        .requirements(wallet, quests)
+        .iter()
+        .all(|(_, met)| *met)
+    {
+        return;
+    }
+    if let Some(cost) = region.unlock_cost
+        && !wallet.spend(cost)
+    {
+        return;
+    }
+
+    unlocks.unlocked.insert(region.id.clone());
+    commands.trigger(RegionUnlocked {
+        region: region.id.clone(),
+    });
+}
@@ -0,0 +1,59 @@
+use bevy::asset::{AssetLoader, LoadContext, io::Reader};
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+
+/**
+Registers an asset type that is deserialized straight from a RON file.
+
+Game data (quests, dialogue, ...) lives in `assets/data` as `<name>.<kind>.ron`, the
+double extension lets several data types share the `.ron` suffix.
+*/
+pub struct RonAssetPlugin<A> {
+    extensions: &'static [&'static str],
+    _marker: PhantomData<A>,
+}
+
+impl<A> RonAssetPlugin<A> {
+    pub fn new(extensions: &'static [&'static str]) -> Self {
+        Self {
+            extensions,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A: Asset + DeserializeOwned> Plugin for RonAssetPlugin<A> {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<A>().register_asset_loader(RonLoader::<A> {
+            extensions: self.extensions,
+            _marker: PhantomData,
+        });
+    }
+}
+
+struct RonLoader<A> {
+    extensions: &'static [&'static str],
+    _marker: PhantomData<A>,
+}
+
+impl<A: Asset + DeserializeOwned> AssetLoader for RonLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<A, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
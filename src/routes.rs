@@ -0,0 +1,311 @@
+use bevy::prelude::*;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+/**
+Roads painted into the mask, as a graph between waypoints.
+
+Waypoints are blobs of the legend's waypoint colour, two of them are linked
+when a road connects them without passing another waypoint. Regions are
+attached to the waypoint closest to their anchor.
+*/
+#[derive(Resource, Debug, Default, Clone)]
+pub struct RouteGraph {
+    nodes: Vec<Vec2>,
+    edges: Vec<Vec<(usize, f32)>>,
+    /// Region id to its anchor point and closest waypoint
+    regions: HashMap<String, (Vec2, Option<usize>)>,
+}
+
+/// Frontier entry for Dijkstra, ordered by smallest cost first, then lowest node
+#[derive(PartialEq)]
+struct Visit {
+    cost: f32,
+    node: usize,
+}
+
+impl Eq for Visit {}
+
+impl Ord for Visit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Equal costs pop in node order so ties always resolve the same way
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then(other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for Visit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl RouteGraph {
+    /**
+    Builds the graph from row-major `width * height` sample masks.
+
+    `to_world` turns a sample position into map coordinates, `step` is the
+    world distance between two neighbouring samples.
+    */
+    pub fn build(
+        width: usize,
+        height: usize,
+        waypoints: &[bool],
+        roads: &[bool],
+        step: f32,
+        to_world: impl Fn(Vec2) -> Vec2,
+    ) -> Self {
+        let neighbours = |cell: usize| {
+            let (x, y) = (cell % width, cell / width);
+            let mut cells = Vec::with_capacity(4);
+            if x > 0 {
+                cells.push(cell - 1);
+            }
+            if x + 1 < width {
+                cells.push(cell + 1);
+            }
+            if y > 0 {
+                cells.push(cell - width);
+            }
+            if y + 1 < height {
+                cells.push(cell + width);
+            }
+            cells
+        };
+
+        // Every connected waypoint blob becomes a node at its centre
+        let mut owner: Vec<Option<usize>> = vec![None; width * height];
+        let mut blobs: Vec<Vec<usize>> = Vec::new();
+        for start in 0..width * height {
+            if !waypoints[start] || owner[start].is_some() {
+                continue;
+            }
+            let node = blobs.len();
+            let mut blob = vec![start];
+            owner[start] = Some(node);
+            let mut i = 0;
+            while i < blob.len() {
+                for next in neighbours(blob[i]) {
+                    if waypoints[next] && owner[next].is_none() {
+                        owner[next] = Some(node);
+                        blob.push(next);
+                    }
+                }
+                i += 1;
+            }
+            blobs.push(blob);
+        }
+
+        let nodes: Vec<Vec2> = blobs
+            .iter()
+            .map(|blob| {
+                let sum: Vec2 = blob
+                    .iter()
+                    .map(|cell| Vec2::new((cell % width) as f32, (cell / width) as f32))
+                    .sum();
+                to_world(sum / blob.len() as f32)
+            })
+            .collect();
+
+        // Walk the roads out of every blob, stopping at the first other waypoint
+        let mut edges: Vec<Vec<(usize, f32)>> = vec![Vec::new(); nodes.len()];
+        for (node, blob) in blobs.iter().enumerate() {
+            let mut distance: HashMap<usize, u32> = blob.iter().map(|cell| (*cell, 0)).collect();
+            let mut queue: VecDeque<usize> = blob.iter().copied().collect();
+
+            while let Some(cell) = queue.pop_front() {
+                let steps = distance[&cell];
+                for next in neighbours(cell) {
+                    if distance.contains_key(&next) {
+                        continue;
+                    }
+                    match owner[next] {
+                        Some(other) if other != node => {
+                            let cost = (steps + 1) as f32 * step;
+                            let known = edges[node].iter_mut().find(|(to, _)| *to == other);
+                            match known {
+                                Some(edge) => edge.1 = edge.1.min(cost),
+                                None => edges[node].push((other, cost)),
+                            }
+                            distance.insert(next, steps + 1);
+                        }
+                        Some(_) => {}
+                        None if roads[next] => {
+                            distance.insert(next, steps + 1);
+                            queue.push_back(next);
+                        }
+                        None => {}
+                    }
+                }
+            }
+        }
+
+        Self {
+            nodes,
+            edges,
+            regions: HashMap::new(),
+        }
+    }
+
+    /// Attaches a region to the waypoint nearest to `anchor`
+    pub fn add_region(&mut self, id: &str, anchor: Vec2) {
+        let nearest = (0..self.nodes.len()).min_by(|a, b| {
+            self.nodes[*a]
+                .distance_squared(anchor)
+                .total_cmp(&self.nodes[*b].distance_squared(anchor))
+        });
+        self.regions.insert(id.to_string(), (anchor, nearest));
+    }
+
+    pub fn anchor(&self, region: &str) -> Option<Vec2> {
+        self.regions.get(region).map(|(anchor, _)| *anchor)
+    }
+
+    /// Dijkstra between two waypoints, returns the points to walk through
+    pub fn shortest_path(&self, from: usize, to: usize) -> Option<Vec<Vec2>> {
+        let mut cost = vec![f32::INFINITY; self.nodes.len()];
+        let mut previous: Vec<Option<usize>> = vec![None; self.nodes.len()];
+        let mut frontier = BinaryHeap::new();
+        cost[from] = 0.;
+        frontier.push(Visit {
+            cost: 0.,
+            node: from,
+        });
+
+        while let Some(Visit {
+            cost: current,
+            node,
+        }) = frontier.pop()
+        {
+            if node == to {
+                break;
+            }
+            if current > cost[node] {
+                continue;
+            }
+            for (next, length) in self.edges[node].iter() {
+                let candidate = current + length;
+                if candidate < cost[*next] {
+                    cost[*next] = candidate;
+                    previous[*next] = Some(node);
+                    frontier.push(Visit {
+                        cost: candidate,
+                        node: *next,
+                    });
+                }
+            }
+        }
+
+        if !cost[to].is_finite() {
+            return None;
+        }
+
+        let mut path = vec![self.nodes[to]];
+        let mut node = to;
+        while let Some(prev) = previous[node] {
+            path.push(self.nodes[prev]);
+            node = prev;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /**
+    Route between two regions: from the first anchor along the roads to the
+    second one. Regions without a road connection are travelled in a straight
+    line.
+    */
+    pub fn route(&self, from: &str, to: &str) -> Option<Vec<Vec2>> {
+        let (from_anchor, from_node) = *self.regions.get(from)?;
+        let (to_anchor, to_node) = *self.regions.get(to)?;
+
+        let roads = match (from_node, to_node) {
+            (Some(from_node), Some(to_node)) => self.shortest_path(from_node, to_node),
+            _ => None,
+        };
+
+        let mut path = vec![from_anchor];
+        path.extend(roads.unwrap_or_default());
+        path.push(to_anchor);
+        Some(path)
+    }
+}
+
+pub fn path_length(path: &[Vec2]) -> f32 {
+    path.windows(2).map(|pair| pair[0].distance(pair[1])).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `W` waypoint, `=` road, anything else is empty, sample positions are used as is
+    fn graph(rows: &[&str]) -> RouteGraph {
+        let cells: Vec<char> = rows.iter().flat_map(|row| row.chars()).collect();
+        let waypoints: Vec<bool> = cells.iter().map(|cell| *cell == 'W').collect();
+        let roads: Vec<bool> = cells.iter().map(|cell| *cell == '=').collect();
+        RouteGraph::build(rows[0].len(), rows.len(), &waypoints, &roads, 1., |point| {
+            point
+        })
+    }
+
+    #[test]
+    fn road_joins_two_waypoints() {
+        let graph = graph(&["W===W"]);
+        assert_eq!(graph.nodes, vec![Vec2::new(0., 0.), Vec2::new(4., 0.)]);
+        assert_eq!(graph.edges[0], vec![(1, 4.)]);
+        assert_eq!(graph.edges[1], vec![(0, 4.)]);
+        assert_eq!(
+            graph.shortest_path(0, 1),
+            Some(vec![Vec2::new(0., 0.), Vec2::new(4., 0.)])
+        );
+    }
+
+    #[test]
+    fn broken_road_has_no_path() {
+        let mut graph = graph(&["W=.=W"]);
+        assert!(graph.edges.iter().all(Vec::is_empty));
+        assert_eq!(graph.shortest_path(0, 1), None);
+
+        // Travelling still works, in a straight line between the anchors
+        graph.add_region("left", Vec2::new(-1., 0.));
+        graph.add_region("right", Vec2::new(5., 0.));
+        assert_eq!(
+            graph.route("left", "right"),
+            Some(vec![Vec2::new(-1., 0.), Vec2::new(5., 0.)])
+        );
+        assert_eq!(graph.route("left", "nowhere"), None);
+    }
+
+    #[test]
+    fn route_follows_the_roads() {
+        let mut graph = graph(&["W==W", "...=", "W==W"]);
+        graph.add_region("camp", Vec2::new(-1., 2.));
+        graph.add_region("mine", Vec2::new(-1., 0.));
+        let path = graph.route("camp", "mine").unwrap();
+        assert_eq!(
+            path,
+            vec![
+                Vec2::new(-1., 2.),
+                Vec2::new(0., 2.),
+                Vec2::new(3., 2.),
+                Vec2::new(3., 0.),
+                Vec2::new(0., 0.),
+                Vec2::new(-1., 0.),
+            ]
+        );
+        assert_eq!(path_length(&path), 1. + 3. + 2. + 3. + 1.);
+    }
+
+    #[test]
+    fn equal_routes_pick_the_same_one() {
+        // Both ways around the square are 4 long
+        let rows = ["W=W", "=.=", "W=W"];
+        let expected = vec![Vec2::new(0., 0.), Vec2::new(2., 0.), Vec2::new(2., 2.)];
+        for _ in 0..10 {
+            assert_eq!(graph(&rows).shortest_path(0, 3), Some(expected.clone()));
+        }
+    }
+}
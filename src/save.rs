@@ -0,0 +1,136 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::Duration;
+
+use crate::achievements::Achievements;
+use crate::dialogue::DialogueFlags;
+use crate::economy::{Inventory, Upgrades, Wallet};
+use crate::mines::MineId;
+use crate::prestige::Prestige;
+use crate::quest::QuestLog;
+use crate::region_unlock::RegionUnlocks;
+use crate::statistics::Statistics;
+use crate::travel::PlayerLocation;
+
+const SAVE_PATH: &str = "save.ron";
+const AUTOSAVE_PERIOD: Duration = Duration::from_secs(30);
+
+pub struct SavePlugin;
+
+/**
+Everything that survives a restart. Missing fields fall back to defaults so
+older saves keep loading when new subsystems are added.
+*/
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct SaveFile {
+    wallet: Wallet,
+    inventory: Inventory,
+    upgrades: Upgrades,
+    prestige: Prestige,
+    quests: QuestLog,
+    dialogue: DialogueFlags,
+    achievements: Achievements,
+    statistics: Statistics,
+    regions: RegionUnlocks,
+    location: PlayerLocation,
+    mine: MineId,
+}
+
+#[derive(Resource)]
+struct Autosave(Timer);
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Autosave(Timer::new(AUTOSAVE_PERIOD, TimerMode::Repeating)))
+            .add_systems(PostStartup, load_game)
+            .add_systems(Last, (autosave, save_on_exit.run_if(on_event::<AppExit>)));
+    }
+}
+
+fn load_game(mut commands: Commands) {
+    let data = match fs::read_to_string(SAVE_PATH) {
+        Ok(data) => data,
+        Err(_) => {
+            println!("No save found, starting a new game");
+            return;
+        }
+    };
+
+    let save: SaveFile = match ron::from_str(&data) {
+        Ok(save) => save,
+        Err(err) => {
+            error!("Failed to parse {}: {}", SAVE_PATH, err);
+            return;
+        }
+    };
+
+    commands.insert_resource(save.wallet);
+    commands.insert_resource(save.inventory);
+    commands.insert_resource(save.upgrades);
+    commands.insert_resource(save.prestige);
+    commands.insert_resource(save.quests);
+    commands.insert_resource(save.dialogue);
+    commands.insert_resource(save.achievements);
+    commands.insert_resource(save.statistics);
+    commands.insert_resource(save.regions);
+    commands.insert_resource(save.location);
+    commands.insert_resource(save.mine);
+}
+
+/// Resources that end up in the save file
+#[derive(SystemParam)]
+struct Persistent<'w> {
+    wallet: Res<'w, Wallet>,
+    inventory: Res<'w, Inventory>,
+    upgrades: Res<'w, Upgrades>,
+    prestige: Res<'w, Prestige>,
+    quests: Res<'w, QuestLog>,
+    dialogue: Res<'w, DialogueFlags>,
+    achievements: Res<'w, Achievements>,
+    statistics: Res<'w, Statistics>,
+    regions: Res<'w, RegionUnlocks>,
+    location: Res<'w, PlayerLocation>,
+    mine: Res<'w, MineId>,
+}
+
+impl Persistent<'_> {
+    fn snapshot(&self) -> SaveFile {
+        SaveFile {
+            wallet: self.wallet.clone(),
+            inventory: self.inventory.clone(),
+            upgrades: self.upgrades.clone(),
+            prestige: self.prestige.clone(),
+            quests: self.quests.clone(),
+            dialogue: self.dialogue.clone(),
+            achievements: self.achievements.clone(),
+            statistics: self.statistics.clone(),
+            regions: self.regions.clone(),
+            location: self.location.clone(),
+            mine: self.mine.clone(),
+        }
+    }
+}
+
+fn write_save(save: &SaveFile) {
+    match ron::ser::to_string_pretty(save, ron::ser::PrettyConfig::default()) {
+        Ok(data) => {
+            if let Err(err) = fs::write(SAVE_PATH, data) {
+                error!("Failed to write {}: {}", SAVE_PATH, err);
+            }
+        }
+        Err(err) => error!("Failed to serialize save: {}", err),
+    }
+}
+
+fn autosave(time: Res<Time>, mut autosave: ResMut<Autosave>, persistent: Persistent) {
+    if autosave.0.tick(time.delta()).just_finished() {
+        write_save(&persistent.snapshot());
+    }
+}
+
+fn save_on_exit(persistent: Persistent) {
+    write_save(&persistent.snapshot());
+}
@@ -1,24 +1,61 @@
-use std::{f32::consts::PI, time::Duration};
+use std::{collections::VecDeque, f32::consts::PI, time::Duration};
 
 use crate::states::{GameState, SceneTransitionState};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use rand::Rng;
+
+/// Whole transition, covering and uncovering the screen take half each
+const DEFAULT_DURATION: Duration = Duration::from_millis(400);
+
+/// Longest hold on black for a scene that never reports ready
+const MAX_HOLD: f32 = 10.;
+
+/// Quick loads don't flash the spinner
+const SPINNER_DELAY: f32 = 0.3;
+
+/// Tiles across the screen for [`TransitionStyle::Dissolve`], rows follow the aspect ratio
+const DISSOLVE_COLUMNS: usize = 32;
 
 pub struct SceneChangePlugin;
 
 #[derive(Component)]
 pub struct SceneChangeOverlay;
 
+/// How the screen gets covered while the scene changes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransitionStyle {
+    /// Fades to a plain colour and back
+    Fade(Color),
+    /// A curtain of the colour slides in from the left and out to the right
+    Wipe(Color),
+    /// Black circle closing on a point in window coordinates, the cursor when `None`
+    Iris(Option<Vec2>),
+    /// Tiles of the colour pop in and out in random order
+    Dissolve(Color),
+}
+
+impl Default for TransitionStyle {
+    fn default() -> Self {
+        TransitionStyle::Fade(Color::BLACK)
+    }
+}
+
 #[derive(Component)]
 pub struct InTransition {
     to: GameState,
     timer: Timer,
+    style: TransitionStyle,
+    spinner: bool,
 }
 
 impl InTransition {
-    pub fn new(to: GameState, duration: Duration) -> Self {
+    pub fn new(to: GameState, duration: Duration, style: TransitionStyle) -> Self {
         Self {
             to,
             timer: Timer::new(duration, TimerMode::Once),
+            style,
+            spinner: true,
         }
     }
 }
@@ -26,17 +63,259 @@ impl InTransition {
 #[derive(Component)]
 pub struct FadeIn {
     timer: Timer,
+    style: TransitionStyle,
 }
 
-#[derive(Event)]
+impl FadeIn {
+    pub fn new(duration: Duration, style: TransitionStyle) -> Self {
+        Self {
+            timer: Timer::new(duration, TimerMode::Once),
+            style,
+        }
+    }
+}
+
+/// What happens to a scene change requested while another one is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChangePolicy {
+    /// Runs once the transitions before it are over
+    Queue,
+    /// Dropped, so mashing a key doesn't pile up transitions
+    #[default]
+    Ignore,
+}
+
+/// How a scene change moves along the [`SceneStack`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Navigation {
+    /// The current scene is left for good
+    #[default]
+    Replace,
+    /// The current scene is remembered so going back returns to it
+    Push,
+    /// Returns to the last remembered scene, see [`SceneBack`]
+    Back,
+}
+
+/**
+Asks [`SceneChangePlugin`] to switch to another `GameState`.
+
+Only one transition runs at a time, its progress is reflected by
+`SceneTransitionState` going Normal -> FadeOut -> Black -> FadeIn -> Normal.
+*/
+#[derive(Event, Debug, Clone)]
 pub struct SceneChange {
-    to: GameState,
+    pub to: GameState,
+    pub style: TransitionStyle,
+    pub duration: Duration,
+    pub policy: ChangePolicy,
+    pub navigation: Navigation,
+    /// Shown while holding on black for a scene that is still loading
+    pub spinner: bool,
 }
 
-impl FadeIn {
-    pub fn new(duration: Duration) -> Self {
+impl SceneChange {
+    pub fn new(to: GameState) -> Self {
         Self {
-            timer: Timer::new(duration, TimerMode::Once),
+            to,
+            style: TransitionStyle::default(),
+            duration: DEFAULT_DURATION,
+            policy: ChangePolicy::default(),
+            navigation: Navigation::default(),
+            spinner: true,
+        }
+    }
+
+    pub fn with_style(mut self, style: TransitionStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: ChangePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_navigation(mut self, navigation: Navigation) -> Self {
+        self.navigation = navigation;
+        self
+    }
+}
+
+/**
+Sent by a scene once it can be shown.
+
+Transitions hold on black after switching the `GameState` until the new scene
+reports ready, scenes usually send it when their `MyLoadingStates` is `Ready`.
+*/
+#[derive(Event, Debug, Clone)]
+pub struct SceneReady {
+    pub scene: GameState,
+}
+
+/// System sending [`SceneReady`] for `scene`
+pub fn report_ready(scene: GameState) -> impl Fn(Commands) + Clone {
+    move |mut commands: Commands| commands.trigger(SceneReady { scene })
+}
+
+/**
+Assets a scene waits for before reporting ready.
+
+Scenes insert it instead of sending [`SceneReady`] themselves when what they
+spawned would otherwise pop in during the fade in. It is sent once every handle
+is loaded with its dependencies, or failed to load.
+*/
+#[derive(Resource, Debug)]
+pub struct SceneLoading {
+    pub scene: GameState,
+    pub handles: Vec<UntypedHandle>,
+}
+
+fn poll_scene_loading(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    loading: Res<SceneLoading>,
+) {
+    let settled = loading.handles.iter().all(|handle| {
+        asset_server.is_loaded_with_dependencies(handle)
+            || asset_server
+                .get_recursive_dependency_load_state(handle)
+                .is_some_and(|state| state.is_failed())
+    });
+    if settled {
+        commands.remove_resource::<SceneLoading>();
+        commands.trigger(SceneReady {
+            scene: loading.scene,
+        });
+    }
+}
+
+/// Scene the transition waits for while on black
+#[derive(Resource, Debug)]
+struct AwaitedScene {
+    scene: GameState,
+    ready: bool,
+    spinner: bool,
+    waited: f32,
+}
+
+#[derive(Component)]
+struct LoadingSpinner;
+
+/// Goes back to the previous scene, sent by Escape and the back button
+#[derive(Event, Debug, Clone)]
+pub struct SceneBack;
+
+/// Scenes to go back to, the current one is not part of it
+#[derive(Resource, Default, Debug)]
+pub struct SceneStack(Vec<GameState>);
+
+impl SceneStack {
+    /// Where going back leads, scenes without history fall back to the map hub
+    pub fn back_target(&self, current: GameState) -> Option<GameState> {
+        self.0
+            .last()
+            .copied()
+            .or((current != GameState::Map).then_some(GameState::Map))
+    }
+
+    /// Every scene from the bottom of the stack up to `current`
+    pub fn breadcrumb(&self, current: GameState) -> Vec<GameState> {
+        let mut crumbs = self.0.clone();
+        crumbs.push(current);
+        crumbs
+    }
+
+    /// Pushing a scene that is already on the stack drops what was above it
+    fn push(&mut self, scene: GameState) {
+        if let Some(i) = self.0.iter().position(|other| *other == scene) {
+            self.0.truncate(i);
+        }
+        self.0.push(scene);
+    }
+}
+
+#[derive(Component)]
+struct Breadcrumb;
+
+#[derive(Component)]
+struct BackButton;
+
+/// Scene changes waiting for the running transition
+#[derive(Resource, Default, Debug)]
+struct PendingChanges(VecDeque<SceneChange>);
+
+/// Run condition for input that shouldn't be handled while the screen is covered
+pub fn not_in_transition(state: Res<State<SceneTransitionState>>) -> bool {
+    *state.get() == SceneTransitionState::Normal
+}
+
+/// Node drawn over the overlay by the current transition
+#[derive(Component)]
+struct TransitionPart;
+
+/// Hole of the iris, centred on a window position
+#[derive(Component)]
+struct IrisHole {
+    centre: Vec2,
+}
+
+/// Dissolve tile, shown once the screen is covered more than `threshold`
+#[derive(Component)]
+struct DissolveTile {
+    threshold: f32,
+}
+
+/// Nodes of the current transition, as the styles animate them
+type TransitionParts<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Node,
+        &'static mut Visibility,
+        Option<&'static IrisHole>,
+        Option<&'static DissolveTile>,
+    ),
+    With<TransitionPart>,
+>;
+
+/// The overlay and the nodes transition styles draw with
+#[derive(SystemParam)]
+struct Cover<'w, 's> {
+    overlay: Query<'w, 's, (Entity, &'static mut BackgroundColor), With<SceneChangeOverlay>>,
+    parts: TransitionParts<'w, 's>,
+    window: Query<'w, 's, &'static Window>,
+}
+
+impl Cover<'_, '_> {
+    fn spawn_parts(&self, commands: &mut Commands, style: &TransitionStyle) {
+        if let Ok((overlay, _)) = self.overlay.single() {
+            spawn_parts(commands, overlay, style, self.window.single().ok());
+        }
+    }
+
+    fn draw(&mut self, style: &TransitionStyle, covered: f32, leaving: bool) {
+        let window = self.window.single().ok();
+        if let Ok((_, mut overlay)) = self.overlay.single_mut() {
+            draw_cover(
+                style,
+                covered,
+                leaving,
+                &mut overlay,
+                &mut self.parts,
+                window,
+            );
+        }
+    }
+
+    fn clear(&mut self) {
+        if let Ok((_, mut overlay)) = self.overlay.single_mut() {
+            overlay.0.set_alpha(0.);
         }
     }
 }
@@ -45,23 +324,43 @@ impl Plugin for SceneChangePlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<SceneTransitionState>()
             .add_event::<SceneChange>()
+            .init_resource::<PendingChanges>()
+            .init_resource::<SceneStack>()
+            .add_observer(go_back)
+            .add_observer(
+                |trigger: Trigger<SceneReady>, awaited: Option<ResMut<AwaitedScene>>| {
+                    let scene = trigger.event().scene;
+                    if let Some(mut awaited) = awaited.filter(|awaited| awaited.scene == scene) {
+                        awaited.ready = true;
+                    }
+                },
+            )
             .add_systems(Startup, setup)
-            .add_systems(Update, (handle_transitions, fade_in, handle_scene_chage))
+            .add_systems(Update, update_breadcrumb)
+            .add_systems(
+                Update,
+                poll_scene_loading
+                    .run_if(resource_exists::<SceneLoading>)
+                    .before(leave_black),
+            )
+            .add_systems(
+                Update,
+                (
+                    handle_scene_chage,
+                    start_transition.run_if(in_state(SceneTransitionState::Normal)),
+                    handle_transitions.run_if(in_state(SceneTransitionState::FadeOut)),
+                    leave_black.run_if(in_state(SceneTransitionState::Black)),
+                    fade_in.run_if(in_state(SceneTransitionState::FadeIn)),
+                )
+                    .chain(),
+            )
             .add_systems(
                 PreUpdate,
-                (|state: Res<State<GameState>>, mut ev_scene_change: EventWriter<SceneChange>| {
-                    let current_state = *state.get();
-                    let new_state = match current_state {
-                        GameState::Mine => GameState::Map,
-                        GameState::Map => GameState::Tavern,
-                        GameState::Tavern => GameState::Mine,
-                    };
-                    ev_scene_change.write(SceneChange { to: new_state });
-                    println!("{:?}", new_state);
-                })
-                .distributive_run_if(
-                    bevy::input::common_conditions::input_just_pressed(KeyCode::Tab),
-                ),
+                (|mut commands: Commands| commands.trigger(SceneBack))
+                    .run_if(bevy::input::common_conditions::input_just_pressed(
+                        KeyCode::Escape,
+                    ))
+                    .run_if(not_in_transition),
             );
     }
 }
@@ -73,63 +372,395 @@ fn setup(mut commands: Commands) {
             height: Val::Percent(100.),
             align_items: AlignItems::Center,
             justify_content: JustifyContent::Center,
+            overflow: Overflow::clip(),
             ..default()
         },
         BackgroundColor(Color::linear_rgba(0., 0., 0., 0.)),
+        // Above every prompt and panel
+        GlobalZIndex(100),
         Pickable::IGNORE,
         SceneChangeOverlay,
     ));
+
+    let crumbs = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.),
+                left: Val::Px(12.),
+                column_gap: Val::Px(12.),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            GlobalZIndex(4),
+            Pickable::IGNORE,
+            Name::new("Breadcrumb"),
+        ))
+        .id();
+
+    commands
+        .spawn((
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(12.), Val::Px(4.)),
+                display: Display::None,
+                ..default()
+            },
+            BackgroundColor(Color::linear_rgba(0.1, 0.15, 0.2, 0.85)),
+            BackButton,
+            ChildOf(crumbs),
+            children![(Text::new("< Back"), Pickable::IGNORE)],
+        ))
+        .observe(
+            |_trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                commands.trigger(SceneBack);
+            },
+        );
+
+    commands.spawn((
+        Text::default(),
+        TextShadow::default(),
+        Pickable::IGNORE,
+        Breadcrumb,
+        ChildOf(crumbs),
+    ));
+}
+
+fn update_breadcrumb(
+    stack: Res<SceneStack>,
+    state: Res<State<GameState>>,
+    mut q_text: Query<&mut Text, With<Breadcrumb>>,
+    mut q_back: Query<&mut Node, With<BackButton>>,
+) {
+    if !stack.is_changed() && !state.is_changed() {
+        return;
+    }
+    let current = *state.get();
+    if let Ok(mut text) = q_text.single_mut() {
+        text.0 = stack
+            .breadcrumb(current)
+            .iter()
+            .map(|scene| format!("{:?}", scene))
+            .collect::<Vec<_>>()
+            .join(" > ");
+    }
+    if let Ok(mut node) = q_back.single_mut() {
+        node.display = match stack.back_target(current) {
+            Some(_) => Display::Flex,
+            None => Display::None,
+        };
+    }
+}
+
+fn go_back(
+    _trigger: Trigger<SceneBack>,
+    stack: Res<SceneStack>,
+    state: Res<State<GameState>>,
+    mut ev_scene_change: EventWriter<SceneChange>,
+) {
+    let Some(to) = stack.back_target(*state.get()) else {
+        return;
+    };
+    ev_scene_change.write(
+        SceneChange::new(to)
+            .with_style(TransitionStyle::Wipe(Color::BLACK))
+            .with_navigation(Navigation::Back),
+    );
+}
+
+/// Spawns the nodes a style draws with, the plain fade only uses the overlay colour
+fn spawn_parts(
+    commands: &mut Commands,
+    overlay: Entity,
+    style: &TransitionStyle,
+    window: Option<&Window>,
+) {
+    match style {
+        TransitionStyle::Fade(_) => {}
+        TransitionStyle::Wipe(color) => {
+            commands.spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    height: Val::Percent(100.),
+                    width: Val::Percent(0.),
+                    ..default()
+                },
+                BackgroundColor(*color),
+                Pickable::IGNORE,
+                TransitionPart,
+                ChildOf(overlay),
+            ));
+        }
+        TransitionStyle::Iris(centre) => {
+            let size = window.map_or(Vec2::new(1920., 1080.), |window| window.size());
+            let centre = centre
+                .or_else(|| window.and_then(Window::cursor_position))
+                .unwrap_or(size / 2.);
+            commands.spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                BorderColor(Color::BLACK),
+                BorderRadius::MAX,
+                Pickable::IGNORE,
+                TransitionPart,
+                IrisHole { centre },
+                ChildOf(overlay),
+            ));
+        }
+        TransitionStyle::Dissolve(color) => {
+            let size = window.map_or(Vec2::new(1920., 1080.), |window| window.size());
+            let rows = ((DISSOLVE_COLUMNS as f32 * size.y / size.x).ceil() as usize).max(1);
+            let mut rng = rand::thread_rng();
+            for row in 0..rows {
+                for column in 0..DISSOLVE_COLUMNS {
+                    commands.spawn((
+                        Node {
+                            position_type: PositionType::Absolute,
+                            left: Val::Percent(column as f32 * 100. / DISSOLVE_COLUMNS as f32),
+                            top: Val::Percent(row as f32 * 100. / rows as f32),
+                            // Slightly bigger than the grid step so no seams show
+                            width: Val::Percent(100. / DISSOLVE_COLUMNS as f32 + 0.1),
+                            height: Val::Percent(100. / rows as f32 + 0.1),
+                            ..default()
+                        },
+                        BackgroundColor(*color),
+                        Visibility::Hidden,
+                        Pickable::IGNORE,
+                        TransitionPart,
+                        DissolveTile {
+                            threshold: rng.gen_range(0.0..1.0),
+                        },
+                        ChildOf(overlay),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/**
+Draws `style` with the screen `covered` from 0 (scene visible) to 1 (hidden).
+
+`leaving` is set while uncovering, so directional styles keep moving the same
+way instead of rewinding.
+*/
+fn draw_cover(
+    style: &TransitionStyle,
+    covered: f32,
+    leaving: bool,
+    overlay: &mut BackgroundColor,
+    q_parts: &mut TransitionParts,
+    window: Option<&Window>,
+) {
+    let eased = (covered * PI / 2.).sin();
+    match style {
+        TransitionStyle::Fade(color) => {
+            overlay.0 = color.with_alpha(eased);
+        }
+        TransitionStyle::Wipe(_) => {
+            for (mut node, ..) in q_parts.iter_mut() {
+                node.width = Val::Percent(eased * 100.);
+                node.left = Val::Percent(if leaving { (1. - eased) * 100. } else { 0. });
+            }
+        }
+        TransitionStyle::Iris(_) => {
+            let size = window.map_or(Vec2::new(1920., 1080.), |window| window.size());
+            for (mut node, _, hole, _) in q_parts.iter_mut() {
+                let Some(hole) = hole else {
+                    continue;
+                };
+                // The ring has to reach the farthest corner even when fully open
+                let reach = [Vec2::ZERO, Vec2::X * size.x, Vec2::Y * size.y, size]
+                    .into_iter()
+                    .map(|corner| corner.distance(hole.centre))
+                    .fold(0., f32::max);
+                let radius = reach * (1. - eased);
+                let outer = radius + reach;
+                node.left = Val::Px(hole.centre.x - outer);
+                node.top = Val::Px(hole.centre.y - outer);
+                node.width = Val::Px(outer * 2.);
+                node.height = Val::Px(outer * 2.);
+                node.border = UiRect::all(Val::Px(reach + 1.));
+            }
+        }
+        TransitionStyle::Dissolve(_) => {
+            for (_, mut visibility, _, tile) in q_parts.iter_mut() {
+                if let Some(tile) = tile {
+                    *visibility = if tile.threshold < covered {
+                        Visibility::Inherited
+                    } else {
+                        Visibility::Hidden
+                    };
+                }
+            }
+        }
+    }
 }
 
 fn fade_in(
     time: Res<Time>,
     mut commands: Commands,
     mut transitions: Query<(Entity, &mut FadeIn)>,
-    mut overlay: Query<&mut BackgroundColor, With<SceneChangeOverlay>>,
+    mut cover: Cover,
+    q_part_entities: Query<Entity, With<TransitionPart>>,
+    mut next_transition: ResMut<NextState<SceneTransitionState>>,
 ) {
     for (entity, mut transition) in transitions.iter_mut() {
         transition.timer.tick(time.delta());
-        let mut clr = overlay.single_mut().expect("not found");
 
         if transition.timer.finished() {
             commands.entity(entity).despawn();
-            clr.0.set_alpha(0.0);
+            for part in q_part_entities.iter() {
+                commands.entity(part).despawn();
+            }
+            cover.clear();
+            next_transition.set(SceneTransitionState::Normal);
             continue;
         }
 
-        let progress = transition.timer.elapsed_secs() / transition.timer.duration().as_secs_f32();
-        let alpha = (progress * PI / 2.).cos();
-        println!("{:?}", progress);
-        clr.0.set_alpha(alpha);
+        cover.draw(&transition.style, 1. - transition.timer.fraction(), true);
     }
 }
 
-fn handle_scene_chage(mut commands: Commands, mut ev_scene_change: EventReader<SceneChange>) {
+fn handle_scene_chage(
+    mut ev_scene_change: EventReader<SceneChange>,
+    mut pending: ResMut<PendingChanges>,
+    state: Res<State<SceneTransitionState>>,
+    q_transitions: Query<(), With<InTransition>>,
+) {
+    let mut busy = *state.get() != SceneTransitionState::Normal
+        || !pending.0.is_empty()
+        || !q_transitions.is_empty();
     for ev in ev_scene_change.read() {
-        commands.spawn(InTransition::new(ev.to, Duration::from_secs_f32(0.2)));
+        if busy && ev.policy == ChangePolicy::Ignore {
+            continue;
+        }
+        pending.0.push_back(ev.clone());
+        busy = true;
     }
 }
 
+fn start_transition(
+    mut commands: Commands,
+    mut pending: ResMut<PendingChanges>,
+    mut stack: ResMut<SceneStack>,
+    state: Res<State<GameState>>,
+    mut next_transition: ResMut<NextState<SceneTransitionState>>,
+    q_transitions: Query<(), With<InTransition>>,
+    cover: Cover,
+) {
+    // The state only switches to FadeOut next frame
+    if !q_transitions.is_empty() {
+        return;
+    }
+    let Some(change) = pending.0.pop_front() else {
+        return;
+    };
+
+    match change.navigation {
+        Navigation::Replace => {}
+        Navigation::Push => stack.push(*state.get()),
+        Navigation::Back => {
+            stack.0.pop();
+        }
+    }
+
+    cover.spawn_parts(&mut commands, &change.style);
+    commands.spawn(InTransition {
+        spinner: change.spinner,
+        ..InTransition::new(change.to, change.duration / 2, change.style)
+    });
+    next_transition.set(SceneTransitionState::FadeOut);
+}
+
 fn handle_transitions(
     time: Res<Time>,
     mut commands: Commands,
     mut transitions: Query<(Entity, &mut InTransition)>,
-    mut overlay: Query<&mut BackgroundColor, With<SceneChangeOverlay>>,
+    mut cover: Cover,
     mut next_state: ResMut<NextState<GameState>>,
+    mut next_transition: ResMut<NextState<SceneTransitionState>>,
 ) {
     for (entity, mut transition) in transitions.iter_mut() {
         transition.timer.tick(time.delta());
-        let mut clr = overlay.single_mut().expect("not found");
 
         if transition.timer.finished() {
+            // Fully covered for the frame the scenes are swapped
+            cover.draw(&transition.style, 1., false);
             next_state.set(transition.to);
+            next_transition.set(SceneTransitionState::Black);
+            // Inserted before the new scene's OnEnter can report ready
+            commands.insert_resource(AwaitedScene {
+                scene: transition.to,
+                ready: false,
+                spinner: transition.spinner,
+                waited: 0.,
+            });
             commands.entity(entity).despawn();
-            commands.spawn(FadeIn::new(Duration::from_secs_f32(0.2)));
+            commands.spawn(FadeIn::new(transition.timer.duration(), transition.style));
             continue;
         }
 
-        let progress = transition.timer.elapsed_secs() / transition.timer.duration().as_secs_f32();
-        let alpha = (progress * PI / 2.).sin();
-        clr.0.set_alpha(alpha);
+        cover.draw(&transition.style, transition.timer.fraction(), false);
+    }
+}
+
+/// Starts revealing the new scene once it is ready, with a spinner while it loads
+fn leave_black(
+    time: Res<Time>,
+    mut commands: Commands,
+    awaited: Option<ResMut<AwaitedScene>>,
+    mut next_transition: ResMut<NextState<SceneTransitionState>>,
+    q_overlay: Query<Entity, With<SceneChangeOverlay>>,
+    mut q_spinner: Query<(Entity, &mut Text), With<LoadingSpinner>>,
+) {
+    let Some(mut awaited) = awaited else {
+        next_transition.set(SceneTransitionState::FadeIn);
+        return;
+    };
+    awaited.waited += time.delta_secs();
+
+    let timed_out = awaited.waited > MAX_HOLD;
+    if timed_out {
+        warn!(
+            "{:?} never reported ready, showing it anyway",
+            awaited.scene
+        );
+    }
+    if awaited.ready || timed_out {
+        commands.remove_resource::<AwaitedScene>();
+        for (spinner, _) in q_spinner.iter() {
+            commands.entity(spinner).despawn();
+        }
+        next_transition.set(SceneTransitionState::FadeIn);
+        return;
+    }
+
+    if !awaited.spinner || awaited.waited < SPINNER_DELAY {
+        return;
+    }
+    let dots = ".".repeat((awaited.waited * 3.) as usize % 4);
+    if let Ok((_, mut text)) = q_spinner.single_mut() {
+        text.0 = format!("Loading{}", dots);
+    } else if let Ok(overlay) = q_overlay.single() {
+        commands.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(32.),
+                bottom: Val::Px(24.),
+                ..default()
+            },
+            Text::new(format!("Loading{}", dots)),
+            TextFont {
+                font_size: 28.,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            Pickable::IGNORE,
+            LoadingSpinner,
+            ChildOf(overlay),
+        ));
     }
 }
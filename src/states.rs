@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(States, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum AppState {
@@ -14,7 +15,7 @@ impl Default for AppState {
     }
 }
 
-#[derive(States, Copy, Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(States, Copy, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum GameState {
     Mine,
     Tavern,
@@ -0,0 +1,210 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::big_number::BigNum;
+use crate::economy::Wallet;
+use crate::game_events::{RegionVisited, RockDestroyed, RockHit};
+use crate::mine_plugin::{Rock, RockKind};
+use crate::scene_change_plugin::not_in_transition;
+use crate::states::GameState;
+
+/// How often the time spent in scenes is written to [`Statistics`], in seconds
+const TIME_FLUSH_PERIOD: f64 = 1.;
+
+pub struct StatisticsPlugin;
+
+/// Lifetime counters, persisted with the save
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct Statistics {
+    pub total_clicks: u64,
+    pub damage_dealt: BigNum,
+    pub rocks_broken: HashMap<RockKind, u32>,
+    pub coins_earned: BigNum,
+    /// Seconds spent in each scene
+    pub time_in_state: HashMap<GameState, f64>,
+    pub scene_changes: u32,
+    pub regions_visited: BTreeSet<String>,
+}
+
+impl Statistics {
+    pub fn total_rocks_broken(&self) -> u32 {
+        self.rocks_broken.values().sum()
+    }
+}
+
+#[derive(Component)]
+struct StatsPanel;
+
+#[derive(Component)]
+struct StatsText;
+
+impl Plugin for StatisticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Statistics>()
+            .add_observer(on_click)
+            .add_observer(on_rock_hit)
+            .add_observer(on_rock_destroyed)
+            .add_observer(on_region_visited)
+            .add_systems(
+                Update,
+                (
+                    track_time,
+                    track_coins,
+                    track_scene_changes,
+                    toggle_stats
+                        .run_if(bevy::input::common_conditions::input_just_pressed(
+                            KeyCode::F2,
+                        ))
+                        .run_if(not_in_transition),
+                    update_stats_text,
+                ),
+            );
+    }
+}
+
+fn on_click(
+    trigger: Trigger<Pointer<Click>>,
+    q_rocks: Query<(), With<Rock>>,
+    mut stats: ResMut<Statistics>,
+) {
+    if q_rocks.contains(trigger.target()) {
+        stats.total_clicks += 1;
+    }
+}
+
+fn on_rock_hit(trigger: Trigger<RockHit>, mut stats: ResMut<Statistics>) {
+    stats.damage_dealt += trigger.event().damage;
+}
+
+fn on_rock_destroyed(trigger: Trigger<RockDestroyed>, mut stats: ResMut<Statistics>) {
+    *stats.rocks_broken.entry(trigger.event().kind).or_insert(0) += 1;
+}
+
+fn on_region_visited(trigger: Trigger<RegionVisited>, mut stats: ResMut<Statistics>) {
+    let region = &trigger.event().region;
+    if !stats.regions_visited.contains(region) {
+        stats.regions_visited.insert(region.clone());
+    }
+}
+
+/// Time not yet added to [`Statistics`]
+#[derive(Default)]
+struct PendingTime {
+    state: Option<GameState>,
+    secs: f64,
+}
+
+/// Flushes in batches, changing `Statistics` every frame would wake up everything watching it
+fn track_time(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    mut pending: Local<PendingTime>,
+    mut stats: ResMut<Statistics>,
+) {
+    let state = *state.get();
+    if let Some(previous) = pending.state
+        && (previous != state || pending.secs >= TIME_FLUSH_PERIOD)
+    {
+        *stats.time_in_state.entry(previous).or_insert(0.) += pending.secs;
+        pending.secs = 0.;
+    }
+    pending.state = Some(state);
+    pending.secs += time.delta_secs_f64();
+}
+
+/// Coins come from many places, so follow the wallet's earned counter instead of each source
+fn track_coins(
+    wallet: Res<Wallet>,
+    mut last: Local<Option<BigNum>>,
+    mut stats: ResMut<Statistics>,
+) {
+    if let Some(last) = *last
+        && wallet.earned > last
+    {
+        stats.coins_earned += wallet.earned - last;
+    }
+    *last = Some(wallet.earned);
+}
+
+fn track_scene_changes(
+    mut transitions: EventReader<StateTransitionEvent<GameState>>,
+    mut stats: ResMut<Statistics>,
+) {
+    for transition in transitions.read() {
+        if transition.exited.is_some() && transition.exited != transition.entered {
+            stats.scene_changes += 1;
+        }
+    }
+}
+
+fn toggle_stats(mut commands: Commands, q_panel: Query<Entity, With<StatsPanel>>) {
+    if let Ok(panel) = q_panel.single() {
+        commands.entity(panel).despawn();
+        return;
+    }
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(20.),
+            left: Val::Px(20.),
+            width: Val::Px(380.),
+            padding: UiRect::all(Val::Px(12.)),
+            ..default()
+        },
+        BackgroundColor(Color::linear_rgba(0.02, 0.03, 0.05, 0.85)),
+        GlobalZIndex(5),
+        Pickable::IGNORE,
+        Name::new("Statistics"),
+        StatsPanel,
+        children![(
+            Text::default(),
+            TextFont {
+                font_size: 18.,
+                ..default()
+            },
+            Pickable::IGNORE,
+            StatsText,
+        )],
+    ));
+}
+
+fn format_duration(secs: f64) -> String {
+    let secs = secs as u64;
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn update_stats_text(stats: Res<Statistics>, mut q_text: Query<&mut Text, With<StatsText>>) {
+    let Ok(mut text) = q_text.single_mut() else {
+        return;
+    };
+
+    let mut lines = vec![
+        "Statistics (F2 to close)".to_string(),
+        String::new(),
+        format!("Clicks: {}", stats.total_clicks),
+        format!("Damage dealt: {}", stats.damage_dealt.floor()),
+        format!("Coins earned: {}", stats.coins_earned),
+        format!("Rocks broken: {}", stats.total_rocks_broken()),
+    ];
+    for kind in [RockKind::Copper, RockKind::Silver, RockKind::Gold] {
+        lines.push(format!(
+            "  {}: {}",
+            kind.name(),
+            stats.rocks_broken.get(&kind).copied().unwrap_or(0)
+        ));
+    }
+    lines.push(format!("Regions visited: {}", stats.regions_visited.len()));
+    lines.push(format!("Scene changes: {}", stats.scene_changes));
+    for state in [GameState::Mine, GameState::Map, GameState::Tavern] {
+        lines.push(format!(
+            "Time in {:?}: {}",
+            state,
+            format_duration(stats.time_in_state.get(&state).copied().unwrap_or(0.))
+        ));
+    }
+
+    text.0 = lines.join("\n");
+}
@@ -0,0 +1,405 @@
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use bevy_simple_screen_boxing::CameraBox;
+
+use crate::big_number::BigNum;
+use crate::dialogue::{DialogueBook, Dialogues, StartDialogue};
+use crate::economy::{Inventory, Upgrades, Wallet};
+use crate::game_events::ItemCrafted;
+use crate::prestige::OpenPrestigeScreen;
+use crate::quest::{QuestBook, QuestLog, QuestStatus, Quests};
+use crate::scene_change_plugin::report_ready;
+use crate::states::GameState;
+use crate::util::despawn_screen;
+
+/// Coins the smith charges for one silver bar
+const SILVER_BAR_COST: u64 = 250;
+
+pub struct TavernPlugin;
+
+#[derive(Component)]
+pub struct TavernSceneTag;
+
+/// Contract board offering quests, rebuilt whenever the quest log changes
+#[derive(Component)]
+struct ContractBoard;
+
+/// Blacksmith upgrades and prestige
+#[derive(Component)]
+struct TavernServices;
+
+/// Characters the player can talk to
+#[derive(Component)]
+struct TavernPatrons;
+
+/// Smith turning coins into silver bars for delivery contracts
+#[derive(Component)]
+struct Smelter;
+
+#[derive(Component)]
+struct WalletText;
+
+#[derive(Component)]
+struct PickaxeText;
+
+#[derive(Component)]
+struct BootsText;
+
+impl Plugin for TavernPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Tavern),
+            (setup, report_ready(GameState::Tavern)),
+        )
+        .add_systems(OnExit(GameState::Tavern), despawn_screen::<TavernSceneTag>)
+        .add_systems(
+            Update,
+            (
+                fill_services,
+                fill_smelter,
+                refresh_board,
+                refresh_patrons,
+                update_wallet,
+                update_pickaxe,
+                update_boots,
+            )
+                .run_if(in_state(GameState::Tavern)),
+        );
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: 0,
+            ..default()
+        },
+        CameraBox::ResolutionIntegerScale {
+            resolution: Vec2::new(1920., 1080.),
+            allow_imperfect_aspect_ratios: true,
+        },
+        RenderLayers::layer(0),
+        TavernSceneTag,
+    ));
+
+    commands.spawn((
+        Node {
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            padding: UiRect::all(Val::Px(40.)),
+            row_gap: Val::Px(20.),
+            ..default()
+        },
+        BackgroundColor(Color::linear_rgb(0.12, 0.06, 0.03)),
+        Name::new("Tavern"),
+        TavernSceneTag,
+        children![
+            (
+                Text::new("Tavern"),
+                TextFont {
+                    font_size: 48.,
+                    ..default()
+                },
+            ),
+            (
+                Text::default(),
+                TextFont {
+                    font_size: 24.,
+                    ..default()
+                },
+                WalletText,
+            ),
+            (
+                Node {
+                    column_gap: Val::Px(16.),
+                    ..default()
+                },
+                Name::new("Services"),
+                TavernServices,
+            ),
+            (
+                Node {
+                    column_gap: Val::Px(16.),
+                    ..default()
+                },
+                Name::new("Patrons"),
+                TavernPatrons,
+            ),
+            (Node::default(), Name::new("Smelter"), Smelter),
+            (
+                Node {
+                    width: Val::Px(900.),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(12.),
+                    padding: UiRect::all(Val::Px(16.)),
+                    ..default()
+                },
+                BackgroundColor(Color::linear_rgb(0.3, 0.18, 0.08)),
+                Name::new("Contract board"),
+                ContractBoard,
+            ),
+        ],
+    ));
+}
+
+fn update_wallet(wallet: Res<Wallet>, mut q_text: Query<(&mut Text, Ref<WalletText>)>) {
+    for (mut text, tag) in q_text.iter_mut() {
+        if tag.is_added() || wallet.is_changed() {
+            text.0 = format!("Coins: {}", wallet.coins);
+        }
+    }
+}
+
+fn fill_services(mut commands: Commands, q_services: Query<Entity, Added<TavernServices>>) {
+    for services in q_services.iter() {
+        commands
+            .spawn((
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(16.), Val::Px(8.)),
+                    ..default()
+                },
+                BackgroundColor(Color::linear_rgb(0.25, 0.25, 0.3)),
+                ChildOf(services),
+                children![(Text::default(), Pickable::IGNORE, PickaxeText)],
+            ))
+            .observe(buy_pickaxe);
+
+        commands
+            .spawn((
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(16.), Val::Px(8.)),
+                    ..default()
+                },
+                BackgroundColor(Color::linear_rgb(0.25, 0.25, 0.3)),
+                ChildOf(services),
+                children![(Text::default(), Pickable::IGNORE, BootsText)],
+            ))
+            .observe(buy_boots);
+
+        commands
+            .spawn((
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(16.), Val::Px(8.)),
+                    ..default()
+                },
+                BackgroundColor(Color::linear_rgb(0.4, 0.15, 0.45)),
+                ChildOf(services),
+                children![(Text::new("Prestige"), Pickable::IGNORE)],
+            ))
+            .observe(
+                |_trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                    commands.trigger(OpenPrestigeScreen);
+                },
+            );
+    }
+}
+
+fn update_pickaxe(upgrades: Res<Upgrades>, mut q_text: Query<(&mut Text, Ref<PickaxeText>)>) {
+    for (mut text, tag) in q_text.iter_mut() {
+        if tag.is_added() || upgrades.is_changed() {
+            text.0 = format!(
+                "Upgrade pickaxe to level {} ({} coins)",
+                upgrades.pickaxe + 1,
+                upgrades.pickaxe_cost()
+            );
+        }
+    }
+}
+
+fn update_boots(upgrades: Res<Upgrades>, mut q_text: Query<(&mut Text, Ref<BootsText>)>) {
+    for (mut text, tag) in q_text.iter_mut() {
+        if tag.is_added() || upgrades.is_changed() {
+            text.0 = format!(
+                "Upgrade boots to level {} ({} coins)",
+                upgrades.boots + 1,
+                upgrades.boots_cost()
+            );
+        }
+    }
+}
+
+fn buy_boots(
+    _trigger: Trigger<Pointer<Click>>,
+    mut wallet: ResMut<Wallet>,
+    mut upgrades: ResMut<Upgrades>,
+) {
+    if wallet.spend(upgrades.boots_cost()) {
+        upgrades.boots += 1;
+    }
+}
+
+fn buy_pickaxe(
+    _trigger: Trigger<Pointer<Click>>,
+    mut wallet: ResMut<Wallet>,
+    mut upgrades: ResMut<Upgrades>,
+) {
+    if wallet.spend(upgrades.pickaxe_cost()) {
+        upgrades.pickaxe += 1;
+    }
+}
+
+fn refresh_patrons(
+    mut commands: Commands,
+    dialogues: Res<Dialogues>,
+    books: Res<Assets<DialogueBook>>,
+    q_patrons: Query<(Entity, Ref<TavernPatrons>)>,
+) {
+    let Ok((patrons, tag)) = q_patrons.single() else {
+        return;
+    };
+    if !tag.is_added() && !books.is_changed() {
+        return;
+    }
+    let Some(book) = books.get(&dialogues.book) else {
+        return;
+    };
+
+    commands.entity(patrons).despawn_related::<Children>();
+
+    for character in book.characters.iter() {
+        let id = character.id.clone();
+        commands
+            .spawn((
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(16.), Val::Px(8.)),
+                    ..default()
+                },
+                BackgroundColor(Color::linear_rgb(0.35, 0.2, 0.1)),
+                ChildOf(patrons),
+                children![(
+                    Text::new(format!("Talk to {}", character.name)),
+                    Pickable::IGNORE
+                )],
+            ))
+            .observe(
+                move |_trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                    commands.trigger(StartDialogue {
+                        character: id.clone(),
+                    });
+                },
+            );
+    }
+}
+
+fn fill_smelter(mut commands: Commands, q_smelter: Query<Entity, Added<Smelter>>) {
+    for smelter in q_smelter.iter() {
+        commands
+            .spawn((
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(16.), Val::Px(8.)),
+                    ..default()
+                },
+                BackgroundColor(Color::linear_rgb(0.25, 0.25, 0.3)),
+                ChildOf(smelter),
+                children![(
+                    Text::new(format!("Smelt a silver bar ({} coins)", SILVER_BAR_COST)),
+                    Pickable::IGNORE
+                )],
+            ))
+            .observe(smelt_silver_bar);
+    }
+}
+
+fn smelt_silver_bar(
+    _trigger: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    mut wallet: ResMut<Wallet>,
+    mut inventory: ResMut<Inventory>,
+) {
+    if wallet.spend(BigNum::from(SILVER_BAR_COST)) {
+        inventory.add("silver_bar", 1);
+        commands.trigger(ItemCrafted {
+            item: "silver_bar".to_string(),
+            count: 1,
+        });
+    }
+}
+
+fn refresh_board(
+    mut commands: Commands,
+    log: Res<QuestLog>,
+    quests: Res<Quests>,
+    books: Res<Assets<QuestBook>>,
+    q_board: Query<(Entity, Ref<ContractBoard>)>,
+) {
+    let Ok((board, tag)) = q_board.single() else {
+        return;
+    };
+    if !tag.is_added() && !log.is_changed() && !books.is_changed() {
+        return;
+    }
+    let Some(book) = books.get(&quests.book) else {
+        return;
+    };
+
+    commands.entity(board).despawn_related::<Children>();
+
+    for quest in book.quests.iter() {
+        let status = log.status(&quest.id);
+        if status == QuestStatus::Completed {
+            continue;
+        }
+
+        let summary = format!(
+            "{}\n{}\n{} - reward {} coins",
+            quest.title,
+            quest.description,
+            quest.objective.describe(),
+            quest.reward.coins
+        );
+
+        let row = commands
+            .spawn((
+                Node {
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ChildOf(board),
+                children![(
+                    Text::new(summary),
+                    TextFont {
+                        font_size: 18.,
+                        ..default()
+                    },
+                )],
+            ))
+            .id();
+
+        match status {
+            QuestStatus::Active(progress) => {
+                commands.spawn((
+                    Text::new(format!("{}/{}", progress, quest.objective.target())),
+                    ChildOf(row),
+                ));
+            }
+            _ => {
+                let id = quest.id.clone();
+                commands
+                    .spawn((
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(16.), Val::Px(8.)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::linear_rgb(0.2, 0.45, 0.15)),
+                        ChildOf(row),
+                        children![(Text::new("Accept"), Pickable::IGNORE)],
+                    ))
+                    .observe(
+                        move |_trigger: Trigger<Pointer<Click>>, mut log: ResMut<QuestLog>| {
+                            log.accept(&id);
+                        },
+                    );
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::map::MapRegions;
+use crate::map_legend::Destination;
+use crate::region_unlock::RegionUnlocks;
+
+/// Seconds between two limited-time events
+const EVENT_INTERVAL: f32 = 180.;
+
+/// How long an event lasts, in seconds
+const EVENT_DURATION: f32 = 60.;
+
+pub struct TimedEventsPlugin;
+
+/// Shown on the map while it runs in its region, purely cosmetic
+#[derive(Debug, Clone)]
+pub struct TimedEvent {
+    pub region: String,
+    pub title: String,
+    pub timer: Timer,
+}
+
+/**
+Limited-time events running on the map.
+
+A new one starts every [`EVENT_INTERVAL`] seconds in a random unlocked mine.
+They are decorative: they only show up as map markers, change nothing in the
+mines and are not saved.
+*/
+#[derive(Resource, Debug)]
+pub struct TimedEvents {
+    pub active: Vec<TimedEvent>,
+    next: Timer,
+}
+
+impl Default for TimedEvents {
+    fn default() -> Self {
+        TimedEvents {
+            active: Vec::new(),
+            next: Timer::from_seconds(EVENT_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+impl TimedEvents {
+    pub fn in_region(&self, region: &str) -> impl Iterator<Item = &TimedEvent> {
+        self.active
+            .iter()
+            .filter(move |event| event.region == region)
+    }
+}
+
+impl Plugin for TimedEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimedEvents>()
+            .add_systems(Update, tick_events);
+    }
+}
+
+fn tick_events(
+    time: Res<Time>,
+    mut events: ResMut<TimedEvents>,
+    regions: Option<Res<MapRegions>>,
+    unlocks: Res<RegionUnlocks>,
+) {
+    let delta = time.delta();
+    for event in events.active.iter_mut() {
+        event.timer.tick(delta);
+    }
+    events.active.retain(|event| !event.timer.finished());
+
+    if !events.next.tick(delta).just_finished() {
+        return;
+    }
+    // Regions are only known once the map has been opened
+    let Some(regions) = regions else {
+        return;
+    };
+    let mines: Vec<_> = regions
+        .defs
+        .iter()
+        .filter(|def| matches!(def.destination, Some(Destination::Mine(_))))
+        .filter(|def| unlocks.is_unlocked(def))
+        .filter(|def| events.in_region(&def.id).next().is_none())
+        .collect();
+    if mines.is_empty() {
+        return;
+    }
+
+    let def = mines[rand::thread_rng().gen_range(0..mines.len())];
+    events.active.push(TimedEvent {
+        region: def.id.clone(),
+        title: format!("Prospectors passing through {}", def.name),
+        timer: Timer::from_seconds(EVENT_DURATION, TimerMode::Once),
+    });
+}
@@ -0,0 +1,305 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::economy::Upgrades;
+use crate::game_events::RegionVisited;
+use crate::map::MapSceneTag;
+use crate::map_legend::{Destination, RegionDef};
+use crate::mines::MineId;
+use crate::routes::{RouteGraph, path_length};
+use crate::scene_change_plugin::{ChangePolicy, Navigation, SceneChange, TransitionStyle};
+use crate::states::GameState;
+use crate::util::despawn_screen;
+
+/// Map units per second without boots
+const TRAVEL_SPEED: f32 = 300.;
+
+pub struct TravelPlugin;
+
+/// Region the player last travelled to, persisted with the save
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerLocation {
+    pub region: String,
+}
+
+impl Default for PlayerLocation {
+    /// Same place the default mine is
+    fn default() -> Self {
+        PlayerLocation {
+            region: "copper_hills".to_string(),
+        }
+    }
+}
+
+/// Marker walking a route on the map, the scene changes once it arrives
+#[derive(Component)]
+struct Traveller {
+    path: Vec<Vec2>,
+    travelled: f32,
+    speed: f32,
+    region: RegionDef,
+}
+
+impl Traveller {
+    fn position(&self) -> Vec2 {
+        let mut left = self.travelled;
+        for pair in self.path.windows(2) {
+            let length = pair[0].distance(pair[1]);
+            if left <= length && length > 0. {
+                return pair[0].lerp(pair[1], left / length);
+            }
+            left -= length;
+        }
+        self.path.last().copied().unwrap_or_default()
+    }
+}
+
+/// Mesh and material shared by every traveller marker
+#[derive(Resource)]
+struct TravellerLook {
+    mesh: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+}
+
+/// Asks the player whether to travel to a map region
+#[derive(Event, Debug, Clone)]
+pub struct OpenTravelPrompt {
+    pub region: RegionDef,
+}
+
+#[derive(Component)]
+struct TravelPrompt;
+
+impl Plugin for TravelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerLocation>()
+            .add_observer(open_prompt)
+            .add_systems(Startup, setup_traveller_look)
+            .add_systems(OnExit(GameState::Map), despawn_screen::<TravelPrompt>)
+            .add_systems(Update, move_traveller.run_if(in_state(GameState::Map)));
+    }
+}
+
+fn setup_traveller_look(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(TravellerLook {
+        mesh: meshes.add(Circle::new(12.)),
+        material: materials.add(Color::linear_rgb(1., 0.85, 0.3)),
+    });
+}
+
+fn open_prompt(
+    trigger: Trigger<OpenTravelPrompt>,
+    mut commands: Commands,
+    q_prompt: Query<Entity, With<TravelPrompt>>,
+    q_traveller: Query<(), With<Traveller>>,
+    graph: Option<Res<RouteGraph>>,
+    location: Res<PlayerLocation>,
+    upgrades: Res<Upgrades>,
+) {
+    if !q_traveller.is_empty() {
+        return;
+    }
+
+    // Clicking another region replaces the question
+    for prompt in q_prompt.iter() {
+        commands.entity(prompt).despawn();
+    }
+
+    let region = trigger.event().region.clone();
+    if region.destination.is_none() {
+        return;
+    }
+
+    let question = if region.id == location.region {
+        format!("You are at {}, go in?", region.name)
+    } else {
+        let duration = graph
+            .as_ref()
+            .and_then(|graph| graph.route(&location.region, &region.id))
+            .map(|path| path_length(&path) / (TRAVEL_SPEED * upgrades.travel_speed_multiplier()))
+            .unwrap_or(0.);
+        format!("Travel to {}? ({:.1} s)", region.name, duration)
+    };
+
+    let screen = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::linear_rgba(0., 0., 0., 0.5)),
+            GlobalZIndex(10),
+            Name::new("Travel"),
+            TravelPrompt,
+        ))
+        .id();
+
+    let panel = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(16.),
+                padding: UiRect::all(Val::Px(24.)),
+                ..default()
+            },
+            BackgroundColor(Color::linear_rgb(0.1, 0.15, 0.2)),
+            ChildOf(screen),
+            children![
+                (
+                    Text::new(region.name.clone()),
+                    TextFont {
+                        font_size: 36.,
+                        ..default()
+                    },
+                ),
+                Text::new(question),
+            ],
+        ))
+        .id();
+
+    let buttons = commands
+        .spawn((
+            Node {
+                column_gap: Val::Px(16.),
+                ..default()
+            },
+            ChildOf(panel),
+        ))
+        .id();
+
+    commands
+        .spawn((
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(16.), Val::Px(8.)),
+                ..default()
+            },
+            BackgroundColor(Color::linear_rgb(0.2, 0.4, 0.6)),
+            ChildOf(buttons),
+            children![(Text::new("Travel"), Pickable::IGNORE)],
+        ))
+        .observe(
+            move |_trigger: Trigger<Pointer<Click>>,
+                  mut commands: Commands,
+                  look: Res<TravellerLook>,
+                  mut ev_scene_change: EventWriter<SceneChange>,
+                  mut location: ResMut<PlayerLocation>,
+                  graph: Option<Res<RouteGraph>>,
+                  upgrades: Res<Upgrades>,
+                  q_prompt: Query<Entity, With<TravelPrompt>>| {
+                for prompt in q_prompt.iter() {
+                    commands.entity(prompt).despawn();
+                }
+
+                let path = graph
+                    .as_ref()
+                    .and_then(|graph| graph.route(&location.region, &region.id))
+                    .filter(|path| path_length(path) > 0.);
+                let Some(path) = path else {
+                    arrive(&mut commands, &region, &mut location, &mut ev_scene_change);
+                    return;
+                };
+
+                commands.spawn((
+                    Mesh2d(look.mesh.clone()),
+                    MeshMaterial2d(look.material.clone()),
+                    Transform::from_translation(path[0].extend(40.)),
+                    Pickable::IGNORE,
+                    Name::new("Traveller"),
+                    Traveller {
+                        path,
+                        travelled: 0.,
+                        speed: TRAVEL_SPEED * upgrades.travel_speed_multiplier(),
+                        region: region.clone(),
+                    },
+                    MapSceneTag,
+                ));
+            },
+        );
+
+    commands
+        .spawn((
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(16.), Val::Px(8.)),
+                ..default()
+            },
+            BackgroundColor(Color::linear_rgb(0.3, 0.3, 0.3)),
+            ChildOf(buttons),
+            children![(Text::new("Cancel"), Pickable::IGNORE)],
+        ))
+        .observe(
+            |_trigger: Trigger<Pointer<Click>>,
+             mut commands: Commands,
+             q_prompt: Query<Entity, With<TravelPrompt>>| {
+                for prompt in q_prompt.iter() {
+                    commands.entity(prompt).despawn();
+                }
+            },
+        );
+}
+
+fn move_traveller(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut ev_scene_change: EventWriter<SceneChange>,
+    mut location: ResMut<PlayerLocation>,
+    mut q_traveller: Query<(Entity, &mut Traveller, &mut Transform)>,
+) {
+    for (entity, mut traveller, mut transform) in q_traveller.iter_mut() {
+        traveller.travelled += traveller.speed * time.delta_secs();
+        transform.translation = traveller.position().extend(transform.translation.z);
+
+        if traveller.travelled >= path_length(&traveller.path) {
+            commands.entity(entity).despawn();
+            arrive(
+                &mut commands,
+                &traveller.region,
+                &mut location,
+                &mut ev_scene_change,
+            );
+        }
+    }
+}
+
+fn arrive(
+    commands: &mut Commands,
+    region: &RegionDef,
+    location: &mut PlayerLocation,
+    ev_scene_change: &mut EventWriter<SceneChange>,
+) {
+    location.region = region.id.clone();
+    commands.trigger(RegionVisited {
+        region: region.id.clone(),
+    });
+
+    let Some(destination) = &region.destination else {
+        return;
+    };
+    if let Destination::Mine(mine) = destination {
+        commands.insert_resource(MineId(mine.clone()));
+    }
+    let change = match destination {
+        Destination::Mine(_) => SceneChange::new(destination.state())
+            .with_style(TransitionStyle::Iris(None))
+            .with_duration(Duration::from_millis(800)),
+        Destination::Tavern => SceneChange::new(destination.state())
+            .with_style(TransitionStyle::Dissolve(Color::BLACK)),
+    };
+    // Arriving must not get lost behind another transition
+    ev_scene_change.write(
+        change
+            .with_policy(ChangePolicy::Queue)
+            .with_navigation(Navigation::Push),
+    );
+}
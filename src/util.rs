@@ -1,3 +1,4 @@
+use avian3d::prelude::{RigidBody, RigidBodyDisabled};
 use bevy::prelude::*;
 
 pub fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
@@ -6,3 +7,100 @@ pub fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut comm
         commands.entity(entity).despawn();
     }
 }
+
+/// What happens to a scene's entities when the player leaves it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retention {
+    Despawn,
+    /// Kept around hidden and inactive, see [`hibernate_screen`]
+    Hibernate,
+}
+
+/// How a hibernated entity was before, put back by [`wake_screen`]
+#[derive(Component, Debug)]
+pub struct Hibernating {
+    visibility: Option<Visibility>,
+    pickable: Option<Pickable>,
+    camera_active: Option<bool>,
+    body_disabled: bool,
+}
+
+/// Everything [`hibernate_screen`] switches off
+type HibernateData = (
+    Entity,
+    Option<&'static mut Visibility>,
+    Option<&'static Pickable>,
+    Option<&'static mut Camera>,
+    Option<&'static AudioSink>,
+    Has<RigidBody>,
+    Has<RigidBodyDisabled>,
+);
+
+type WakeData = (
+    Entity,
+    &'static Hibernating,
+    Option<&'static mut Visibility>,
+    Option<&'static mut Camera>,
+    Option<&'static AudioSink>,
+);
+
+/**
+Hides and deactivates a screen instead of despawning it.
+
+Entities are hidden and not pickable, cameras stop rendering, sounds are
+paused and rigid bodies stop simulating until [`wake_screen`] runs.
+*/
+pub fn hibernate_screen<T: Component>(
+    mut commands: Commands,
+    mut q_screen: Query<HibernateData, (With<T>, Without<Hibernating>)>,
+) {
+    for (entity, visibility, pickable, camera, sink, body, body_disabled) in q_screen.iter_mut() {
+        let mut hibernating = Hibernating {
+            visibility: None,
+            pickable: pickable.cloned(),
+            camera_active: None,
+            body_disabled,
+        };
+        if let Some(mut visibility) = visibility {
+            hibernating.visibility = Some(*visibility);
+            *visibility = Visibility::Hidden;
+        }
+        if let Some(mut camera) = camera {
+            hibernating.camera_active = Some(camera.is_active);
+            camera.is_active = false;
+        }
+        if let Some(sink) = sink {
+            sink.pause();
+        }
+
+        let mut entity = commands.entity(entity);
+        entity.insert((hibernating, Pickable::IGNORE));
+        if body {
+            entity.insert(RigidBodyDisabled);
+        }
+    }
+}
+
+pub fn wake_screen<T: Component>(mut commands: Commands, mut q_screen: Query<WakeData, With<T>>) {
+    for (entity, hibernating, visibility, camera, sink) in q_screen.iter_mut() {
+        if let (Some(mut visibility), Some(before)) = (visibility, hibernating.visibility) {
+            *visibility = before;
+        }
+        if let (Some(mut camera), Some(active)) = (camera, hibernating.camera_active) {
+            camera.is_active = active;
+        }
+        if let Some(sink) = sink {
+            sink.play();
+        }
+
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.remove::<Hibernating>();
+        match hibernating.pickable.clone() {
+            Some(pickable) => entity_commands.insert(pickable),
+            None => entity_commands.remove::<Pickable>(),
+        };
+        if !hibernating.body_disabled {
+            entity_commands.remove::<RigidBodyDisabled>();
+        }
+    }
+}